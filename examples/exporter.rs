@@ -1,5 +1,4 @@
-use image::{ColorType, save_buffer};
-use sg_image_reader::{SgFile, VecImageBuilderFactory};
+use sg_image_reader::SgFile;
 use std::fs;
 use std::io::{Error, ErrorKind, Result, stdin};
 use std::path::PathBuf;
@@ -20,7 +19,7 @@ fn run() -> Result<()> {
 
     for path in paths {
         if let Ok(dir) = path {
-            if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3")) {
+            if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3") || ext.eq("sg2")) {
                 println!("Unpacking {:?}", dir.path());
                 let start = Instant::now();
                 let mut path_buf = PathBuf::new();
@@ -28,15 +27,11 @@ fn run() -> Result<()> {
                 path_buf.push(dir.file_name());
                 fs::create_dir_all(path_buf.clone())?;
 
-                match SgFile::load_fully(dir.path(), &VecImageBuilderFactory) {
+                match SgFile::load_fully_as_images(dir.path()) {
                     Err(err) => println!("Failed to load: {err:?}"),
-                    Ok((sg_file, pixels)) => {
-                        for i in 0..pixels.len() {
-                            let image = &sg_file.images[i];
-                            let width = image.width as u32;
-                            let height = image.height as u32;
-
-                            if width == 0 || height == 0 {
+                    Ok((_, images)) => {
+                        for (i, image) in images.iter().enumerate() {
+                            if image.width() == 0 || image.height() == 0 {
                                 continue;
                             }
 
@@ -44,7 +39,7 @@ fn run() -> Result<()> {
                             file_path.push(format!("{i}"));
                             file_path.set_extension("png");
 
-                            let result = save_buffer(file_path, &pixels[i], width, height, ColorType::Rgba8);
+                            let result = image.save(file_path);
                             result.map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
                         }
                     }
@@ -14,11 +14,18 @@ fn run() -> Result<()> {
 
     for path in paths {
         if let Ok(dir) = path {
-            if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3")) {
+            if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3") || ext.eq("sg2")) {
                 println!("Verifying {:?}", dir.path());
                 let start = Instant::now();
-                if let Err(err) = SgFile::load_fully(dir.path(), &VecImageBuilderFactory) {
-                    println!("Failed to load: {err:?}");
+                match SgFile::load_fully_lenient(dir.path(), &VecImageBuilderFactory) {
+                    Err(err) => println!("Failed to load: {err:?}"),
+                    Ok((sg_file, images)) => {
+                        let failures: Vec<_> = images.iter().filter_map(|image| image.as_ref().err()).collect();
+                        for err in &failures {
+                            println!("Failed to load image: {err:?}");
+                        }
+                        println!("Loaded {} out of {} images", images.len() - failures.len(), sg_file.images.len());
+                    }
                 };
                 let elapsed_time = start.elapsed();
                 println!("Finished in {}ms", elapsed_time.as_millis());
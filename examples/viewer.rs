@@ -29,7 +29,7 @@ pub const SELECT_IMAGE: Selector<u32> = Selector::new("select-sg-image");
 
 fn build_app() -> impl Widget<AppData> {
     // Create the button to open a filk
-    let open_dialog_options = FileDialogOptions::new().allowed_types(vec![FileSpec::new("SG3", &["sg3"])]);
+    let open_dialog_options = FileDialogOptions::new().allowed_types(vec![FileSpec::new("SG3", &["sg3"]), FileSpec::new("SG2", &["sg2"])]);
 
     let images = Flex::column()
         .with_child(
@@ -82,7 +82,7 @@ fn build_app() -> impl Widget<AppData> {
                         add_row("x_offset", format!("{:?}", image.x_offset));
                         add_row("y_offset", format!("{:?}", image.y_offset));
                         add_row("is_reversible", format!("{:?}", image.is_reversible));
-                        add_row("image_type", format!("{:?}", image.image_type));
+                        add_row("image_type", format!("{:?} ({:?})", image.image_type, image.kind()));
                         add_row("flags", format!("{:?}", image.flags));
                         add_row("anim_speed_id", format!("{:?}", image.anim_speed_id));
                         add_row("alpha_offset", format!("{:?}", image.alpha_offset));
@@ -189,10 +189,10 @@ impl AppDelegate<AppData> for Delegate {
 
         if let Some(image_id) = cmd.get(SELECT_IMAGE) {
             if let Some(LoadedFile(file)) = &data.loaded_file {
-                let image = &file.images[*image_id as usize];
-                let path = file.get_555_file_path(image.album_id as usize, image.is_external());
+                let resolved = file.resolve_mirror(&file.images[*image_id as usize]).expect("Failed to resolve mirrored image.");
+                let path = file.get_555_file_path(resolved.image.album_id as usize, resolved.image.is_external()).expect("Failed to find file.");
                 let mut reader = BufReader::new(File::open(path).expect("Failed to open file."));
-                let pixels = image.load_image(&mut reader, &VecImageBuilderFactory).expect("Failed to get pixel data.");
+                let pixels = resolved.load_image(&mut reader, &VecImageBuilderFactory).expect("Failed to get pixel data.");
                 data.current_image = Some(*image_id as usize);
                 data.pixels = Vector::from(pixels);
                 return Handled::Yes;
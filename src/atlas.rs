@@ -0,0 +1,213 @@
+use crate::{DecodeOptions, Result, SgFile, SgImageError, SgImageMetadata, VecImageBuilderFactory};
+use std::collections::BTreeMap;
+
+/// Packs decoded images into RGBA atlas pages.
+///
+/// Images are placed in rows (shelves), tallest first, starting a new page when the current one is full.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasPacker {
+    pub page_width: u32,
+    pub page_height: u32,
+    /// Transparent pixels left between the images
+    pub padding: u32,
+}
+
+/// Result of packing images, the pages along with the position of each image.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Atlas {
+    pub pages: Vec<AtlasPage>,
+    /// Positions of the images, keyed by image id
+    pub rects: BTreeMap<u32, AtlasRect>,
+}
+
+/// Single page of an atlas, with pixels in the same layout as produced by [`VecImageBuilderFactory`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AtlasPage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Position of an image in an atlas, along with its anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtlasRect {
+    pub image_id: u32,
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u16,
+    pub height: u16,
+    pub x_offset: u16,
+    pub y_offset: u16,
+}
+
+impl AtlasPacker {
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        AtlasPacker { page_width, page_height, padding: 0 }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Pack the given images along with their pixel data.
+    ///
+    /// Images without pixels are left out, images not fitting on a page result in an error.
+    pub fn pack<'a, I: IntoIterator<Item = (&'a SgImageMetadata, &'a [u8])>>(&self, images: I) -> Result<Atlas> {
+        let mut images: Vec<_> = images.into_iter().filter(|(image, _)| image.width != 0 && image.height != 0).collect();
+
+        for (image, pixels) in &images {
+            let expected = image.width as u64 * image.height as u64 * 4;
+
+            if pixels.len() as u64 != expected {
+                let err = SgImageError::ImageDataLengthMismatch { expected, actual: pixels.len() as u64 };
+                return Err(err.in_image(image.id, image.album_id, image.data_offset()));
+            }
+
+            if image.width as u32 > self.page_width || image.height as u32 > self.page_height {
+                let err = SgImageError::InvalidDimensions { width: image.width, height: image.height };
+                return Err(err.in_image(image.id, image.album_id, image.data_offset()));
+            }
+        }
+
+        // Shelf packing works best with the tallest images first
+        images.sort_by(|(a, _), (b, _)| b.height.cmp(&a.height).then(b.width.cmp(&a.width)));
+
+        let mut atlas = Atlas::default();
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for (image, pixels) in images {
+            let (width, height) = (image.width as u32, image.height as u32);
+
+            if x + width > self.page_width {
+                x = 0;
+                y += shelf_height + self.padding;
+                shelf_height = 0;
+            }
+
+            if atlas.pages.is_empty() || y + height > self.page_height {
+                atlas.pages.push(AtlasPage::new(self.page_width, self.page_height));
+                (x, y, shelf_height) = (0, 0, 0);
+            }
+
+            let page = atlas.pages.len() - 1;
+            atlas.pages[page].draw(x, y, width, pixels);
+
+            let rect = AtlasRect {
+                image_id: image.id,
+                page,
+                x,
+                y,
+                width: image.width,
+                height: image.height,
+                x_offset: image.x_offset,
+                y_offset: image.y_offset,
+            };
+            atlas.rects.insert(image.id, rect);
+
+            x += width + self.padding;
+            shelf_height = shelf_height.max(height);
+        }
+
+        Ok(atlas)
+    }
+}
+
+impl AtlasPage {
+    fn new(width: u32, height: u32) -> Self {
+        AtlasPage { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    fn draw(&mut self, x: u32, y: u32, width: u32, pixels: &[u8]) {
+        let row_length = width as usize * 4;
+
+        for (row, data) in pixels.chunks_exact(row_length).enumerate() {
+            let start = ((y as usize + row) * self.width as usize + x as usize) * 4;
+            self.pixels[start..start + row_length].copy_from_slice(data);
+        }
+    }
+}
+
+impl SgFile {
+    /// Load pixel data of the given images (e.g. the images of an album) decoded using the given options and pack them into an atlas.
+    pub fn pack_atlas<'a, I: IntoIterator<Item = &'a SgImageMetadata>>(&self, packer: &AtlasPacker, images: I, options: &DecodeOptions) -> Result<Atlas> {
+        let images: Vec<_> = images.into_iter().collect();
+        let pixels = self.load_selected_image_data(&images, &VecImageBuilderFactory, options)?;
+
+        // Pixel data of mirrored images matches the dimensions of the image they mirror, but is placed under their own id
+        let images = images
+            .into_iter()
+            .map(|image| self.resolve_mirror(image).map(|resolved| SgImageMetadata { id: image.id, ..resolved.image.clone() }))
+            .collect::<Result<Vec<_>>>()?;
+
+        packer.pack(images.iter().zip(pixels.iter().map(Vec::as_slice)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(id: u32, width: u16, height: u16) -> (SgImageMetadata, Vec<u8>) {
+        let pixels = vec![id as u8; width as usize * height as usize * 4];
+        (SgImageMetadata { id, width, height, ..Default::default() }, pixels)
+    }
+
+    #[test]
+    fn packs_images_without_overlap() {
+        let images = [image(1, 6, 4), image(2, 4, 8), image(3, 5, 5), image(4, 10, 3), image(5, 3, 3)];
+        let packer = AtlasPacker::new(12, 12).with_padding(1);
+
+        let atlas = packer.pack(images.iter().map(|(image, pixels)| (image, pixels.as_slice()))).unwrap();
+
+        assert_eq!(atlas.rects.len(), images.len());
+
+        for rect in atlas.rects.values() {
+            let page = &atlas.pages[rect.page];
+            assert!(rect.x + rect.width as u32 <= page.width && rect.y + rect.height as u32 <= page.height);
+
+            // Every pixel of the image is drawn, so overlapping images would overwrite each other
+            for y in rect.y..rect.y + rect.height as u32 {
+                for x in rect.x..rect.x + rect.width as u32 {
+                    assert_eq!(page.pixels[((y * page.width + x) * 4) as usize], rect.image_id as u8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn starts_new_pages_and_keeps_anchors() {
+        let (mut first, first_pixels) = image(1, 8, 8);
+        let (mut second, second_pixels) = image(2, 8, 6);
+        let (empty, _) = image(3, 0, 5);
+        (first.x_offset, first.y_offset) = (3, 7);
+        second.x_offset = 1;
+
+        let atlas = AtlasPacker::new(10, 10).pack([(&first, first_pixels.as_slice()), (&second, second_pixels.as_slice()), (&empty, &[][..])]).unwrap();
+
+        assert_eq!(atlas.pages.len(), 2);
+        assert_eq!(atlas.rects.len(), 2);
+        assert_eq!(atlas.rects[&1], AtlasRect { image_id: 1, page: 0, x: 0, y: 0, width: 8, height: 8, x_offset: 3, y_offset: 7 });
+        assert_eq!(atlas.rects[&2], AtlasRect { image_id: 2, page: 1, x: 0, y: 0, width: 8, height: 6, x_offset: 1, y_offset: 0 });
+    }
+
+    #[test]
+    fn rejects_pixel_data_not_matching_the_image() {
+        let (image, pixels) = image(1, 2, 2);
+
+        let err = AtlasPacker::new(12, 12).pack([(&image, &pixels[4..])]).unwrap_err();
+
+        assert!(matches!(err, SgImageError::Image { id: 1, .. }), "{err:?}");
+    }
+
+    #[test]
+    fn rejects_images_larger_than_a_page() {
+        let (image, pixels) = image(1, 13, 2);
+
+        let err = AtlasPacker::new(12, 12).pack([(&image, pixels.as_slice())]).unwrap_err();
+
+        assert!(matches!(err, SgImageError::Image { id: 1, .. }), "{err:?}");
+    }
+}
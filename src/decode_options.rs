@@ -0,0 +1,127 @@
+use crate::TileSize;
+
+/// Colour used by the games to mark transparent pixels.
+pub const TRANSPARENT_KEY_COLOUR: u16 = 0xf81f;
+
+/// Settings used when decoding pixel data.
+///
+/// The format of the resulting pixels and how their channels are expanded are chosen by the [`crate::ImageBuilderFactory`] instead.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodeOptions {
+    /// Tile sizes tried for isometric images that do not store their size in tiles
+    ///
+    /// If empty, [`TileSize::DEFAULTS`] are used, or the tile sizes of the file when loading through [`crate::SgFile`].
+    pub tile_sizes: Vec<TileSize>,
+    /// Colour of pixels left transparent, `None` to draw every pixel
+    ///
+    /// Keyed pixels are reported to [`crate::ImageBuilder::set_keyed_pixel`] instead.
+    pub key_colour: Option<u16>,
+    /// Whether the key colour applies to plain images, some of which legitimately contain it
+    pub key_plain_images: bool,
+    /// Whether mirrored images (see `invert_offset` of [`crate::SgImageMetadata`]) are flipped, otherwise they are left as the image they mirror
+    pub mirror: bool,
+    /// Whether alpha masks are applied, otherwise images with alpha masks are fully opaque
+    pub apply_alpha: bool,
+}
+
+impl DecodeOptions {
+    pub fn with_tile_sizes(mut self, tile_sizes: Vec<TileSize>) -> Self {
+        self.tile_sizes = tile_sizes;
+        self
+    }
+
+    pub fn with_key_colour(mut self, key_colour: Option<u16>) -> Self {
+        self.key_colour = key_colour;
+        self
+    }
+
+    pub fn with_key_plain_images(mut self, key_plain_images: bool) -> Self {
+        self.key_plain_images = key_plain_images;
+        self
+    }
+
+    pub fn with_mirror(mut self, mirror: bool) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    pub fn with_apply_alpha(mut self, apply_alpha: bool) -> Self {
+        self.apply_alpha = apply_alpha;
+        self
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions { tile_sizes: Vec::new(), key_colour: Some(TRANSPARENT_KEY_COLOUR), key_plain_images: true, mirror: true, apply_alpha: true }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodeOptions, ImageBuilder, ImageBuilderFactory, SgImageMetadata, TRANSPARENT_KEY_COLOUR, VecImageBuilderFactory};
+
+    const RED: u16 = 0x7c00;
+    const GREEN: u16 = 0x03e0;
+
+    /// Plain image of a magenta, red and green pixel.
+    fn plain_image() -> (SgImageMetadata, Vec<u8>) {
+        let data = [TRANSPARENT_KEY_COLOUR, RED, GREEN].iter().flat_map(|colour| colour.to_le_bytes()).collect();
+        (SgImageMetadata { width: 3, height: 1, length: 6, ..Default::default() }, data)
+    }
+
+    fn decode(options: &DecodeOptions) -> Vec<u8> {
+        let (image, data) = plain_image();
+        image.load_image_from_raw_data_with_options(&data, &VecImageBuilderFactory, options).unwrap()
+    }
+
+    /// Records the pixels reported as keyed.
+    struct KeyedPixels(Vec<(usize, u16)>);
+
+    impl ImageBuilder<Vec<(usize, u16)>> for KeyedPixels {
+        fn set_pixel_by_pos(&mut self, _position: usize, _data: [u8; 4]) {}
+
+        fn set_keyed_pixel(&mut self, position: usize, colour: u16) {
+            self.0.push((position, colour));
+        }
+
+        fn set_alpha(&mut self, _position: usize, _alpha: u8) {}
+
+        fn flip_horizontal(&mut self) {}
+
+        fn build(self) -> Vec<(usize, u16)> {
+            self.0
+        }
+    }
+
+    struct KeyedPixelsFactory;
+
+    impl ImageBuilderFactory<Vec<(usize, u16)>> for KeyedPixelsFactory {
+        type Builder = KeyedPixels;
+
+        fn new_builder(&self, _width: u16, _height: u16) -> Self::Builder {
+            KeyedPixels(Vec::new())
+        }
+    }
+
+    #[test]
+    fn key_colour_options() {
+        let (magenta, red, green) = ([0xf0, 0, 0xf8, 0xff], [0xf8, 0, 0, 0xff], [0, 0xf8, 0, 0xff]);
+
+        assert_eq!(decode(&DecodeOptions::default()), [[0; 4], red, green].concat());
+        assert_eq!(decode(&DecodeOptions::default().with_key_colour(None)), [magenta, red, green].concat());
+        assert_eq!(decode(&DecodeOptions::default().with_key_colour(Some(RED))), [magenta, [0; 4], green].concat());
+        assert_eq!(decode(&DecodeOptions::default().with_key_plain_images(false)), [magenta, red, green].concat());
+    }
+
+    #[test]
+    fn keyed_pixels_are_reported_to_the_builder() {
+        let (image, data) = plain_image();
+        let keyed = |options: &DecodeOptions| image.load_image_from_raw_data_with_options(&data, &KeyedPixelsFactory, options).unwrap();
+
+        assert_eq!(keyed(&DecodeOptions::default()), [(0, TRANSPARENT_KEY_COLOUR)]);
+        assert_eq!(keyed(&DecodeOptions::default().with_key_colour(Some(GREEN))), [(2, GREEN)]);
+        assert_eq!(keyed(&DecodeOptions::default().with_key_plain_images(false)), []);
+    }
+}
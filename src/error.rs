@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::io::Error;
+use std::path::{Path, PathBuf};
 use std::str::Utf8Error;
 
 pub type Result<T> = std::result::Result<T, SgImageError>;
@@ -7,20 +8,72 @@ pub type Result<T> = std::result::Result<T, SgImageError>;
 #[derive(Debug)]
 pub enum SgImageError {
     InvalidHeader,
-    ImageDataLengthMismatch,
+    UnsupportedVersion(u32),
+    FileSizeMismatch { expected: u64, actual: u64 },
+    ImageDataLengthMismatch { expected: u64, actual: u64 },
+    InvalidDimensions { width: u16, height: u16 },
     UnknownImageType(u16),
+    NotIsometric(u16),
+    InvalidInvertOffset(u32),
+    UnresolvedMirror(u32),
+    PixelOutOfBounds(usize),
+    InvalidPath(PathBuf),
+    FileNotFound { filename: String, candidates: Vec<PathBuf> },
+    StringTooLong(String),
+    TooManyAlbums(usize),
+    TooManyImages(usize),
+    UnknownAlbum(usize),
     IoError(Error),
     Utf8Error(Utf8Error),
+    /// Error encountered while processing an album record
+    Album { id: u32, source: Box<SgImageError> },
+    /// Error encountered while processing an image, `offset` is the position in the stream when it happened
+    Image { id: u32, album_id: u8, offset: u64, source: Box<SgImageError> },
+    /// Error encountered while processing a file
+    File { path: PathBuf, source: Box<SgImageError> },
+}
+
+impl SgImageError {
+    pub(crate) fn in_album(self, id: u32) -> Self {
+        SgImageError::Album { id, source: Box::new(self) }
+    }
+
+    pub(crate) fn in_image(self, id: u32, album_id: u8, offset: u64) -> Self {
+        SgImageError::Image { id, album_id, offset, source: Box::new(self) }
+    }
+
+    pub(crate) fn in_file(self, path: &Path) -> Self {
+        SgImageError::File { path: path.to_path_buf(), source: Box::new(self) }
+    }
 }
 
 impl Display for SgImageError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             SgImageError::InvalidHeader => write!(f, "invalid header encountered"),
-            SgImageError::ImageDataLengthMismatch => write!(f, "data length mismatch detected"),
-            SgImageError::UnknownImageType(_) => write!(f, "unknown image type encountered"),
+            SgImageError::UnsupportedVersion(version) => write!(f, "unsupported file version: {:#x}", version),
+            SgImageError::FileSizeMismatch { expected, actual } => write!(f, "file size mismatch detected: expected {} bytes, found {}", expected, actual),
+            SgImageError::ImageDataLengthMismatch { expected, actual } => write!(f, "data length mismatch detected: expected {}, found {}", expected, actual),
+            SgImageError::InvalidDimensions { width, height } => write!(f, "invalid image dimensions: {}x{}", width, height),
+            SgImageError::UnknownImageType(image_type) => write!(f, "unknown image type encountered: {}", image_type),
+            SgImageError::NotIsometric(image_type) => write!(f, "image of type {} is not isometric", image_type),
+            SgImageError::InvalidInvertOffset(id) => write!(f, "image {} is a mirror of an image that does not precede it", id),
+            SgImageError::UnresolvedMirror(id) => write!(f, "image {} is a mirror and has to be resolved to the image it mirrors", id),
+            SgImageError::PixelOutOfBounds(position) => write!(f, "pixel data out of image bounds at position {}", position),
+            SgImageError::InvalidPath(path) => write!(f, "invalid path: {}", path.display()),
+            SgImageError::FileNotFound { filename, candidates } => {
+                let candidates: Vec<_> = candidates.iter().map(|path| path.display().to_string()).collect();
+                write!(f, "file {} not found, tried: {}", filename, candidates.join(", "))
+            }
+            SgImageError::StringTooLong(value) => write!(f, "string too long to be written: {}", value),
+            SgImageError::TooManyAlbums(count) => write!(f, "too many albums: {}", count),
+            SgImageError::TooManyImages(count) => write!(f, "too many images for the image records of the file: {}", count),
+            SgImageError::UnknownAlbum(id) => write!(f, "image refers to an unknown album: {}", id),
             SgImageError::IoError(err) => write!(f, "IO error encountered: {}", err),
             SgImageError::Utf8Error(_) => write!(f, "error encountered when reading UTF8 string"),
+            SgImageError::Album { id, .. } => write!(f, "failed to process album {}", id),
+            SgImageError::Image { id, album_id, offset, .. } => write!(f, "failed to process image {} of album {} at offset {}", id, album_id, offset),
+            SgImageError::File { path, .. } => write!(f, "failed to process file {}", path.display()),
         }
     }
 }
@@ -31,4 +84,13 @@ impl From<Error> for SgImageError {
     }
 }
 
-impl std::error::Error for SgImageError {}
+impl std::error::Error for SgImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SgImageError::IoError(err) => Some(err),
+            SgImageError::Utf8Error(err) => Some(err),
+            SgImageError::Album { source, .. } | SgImageError::Image { source, .. } | SgImageError::File { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{SgAlbum, TileSize};
+
+/// Version of Caesar 3 sg2 files.
+pub(crate) const SG2_VERSION: u32 = 0xd3;
+/// Version of sg3 files without alpha masks.
+pub(crate) const SG3_VERSION: u32 = 0xd5;
+/// Version of sg3 files with alpha masks (Emperor).
+pub(crate) const ALPHA_VERSION: u32 = 0xd6;
+pub(crate) const SUPPORTED_VERSIONS: [u32; 3] = [SG2_VERSION, SG3_VERSION, ALPHA_VERSION];
+
+/// Game a sg file belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Game {
+    Caesar3,
+    Pharaoh,
+    Zeus,
+    Poseidon,
+    Emperor,
+    #[default]
+    Unknown,
+}
+
+/// Settings of the sg format that differ between games and versions.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameProfile {
+    pub game: Game,
+    /// Whether the file is a Caesar 3 sg2 file, with a fixed file size and its 555 files in a sub folder
+    pub is_sg2: bool,
+    /// Number of album records in the file, used or not
+    pub album_records: usize,
+    /// Whether image records contain the offset and length of an alpha mask
+    pub has_alpha: bool,
+    /// Tile sizes tried for isometric images that do not store their size in tiles
+    pub tile_sizes: Vec<TileSize>,
+}
+
+impl Game {
+    /// Game implied by the version alone, `Unknown` for versions shared by multiple games.
+    pub fn from_version(version: u32) -> Game {
+        match version {
+            SG2_VERSION => Game::Caesar3,
+            ALPHA_VERSION => Game::Emperor,
+            _ => Game::Unknown,
+        }
+    }
+
+    /// Detect the game from the version, falling back to the names of the sg file and its albums (e.g. `Zeus_General`).
+    pub fn detect(version: u32, filename: &str, albums: &[SgAlbum]) -> Game {
+        let game = Game::from_version(version);
+
+        if game != Game::Unknown {
+            return game;
+        }
+
+        let names = std::iter::once(filename).chain(albums.iter().map(|album| album.external_filename.as_str()));
+
+        for name in names {
+            let name = name.to_ascii_lowercase();
+
+            if name.starts_with("poseidon") {
+                return Game::Poseidon;
+            } else if name.starts_with("zeus") {
+                return Game::Zeus;
+            } else if name.starts_with("pharaoh") {
+                return Game::Pharaoh;
+            }
+        }
+
+        Game::Unknown
+    }
+}
+
+impl GameProfile {
+    pub fn new(game: Game, version: u32) -> Self {
+        let is_sg2 = version == SG2_VERSION;
+        let album_records = if is_sg2 { 100 } else { 200 };
+
+        let tile_sizes = match game {
+            Game::Caesar3 | Game::Pharaoh => vec![TileSize::SMALL],
+            _ => TileSize::DEFAULTS.to_vec(),
+        };
+
+        GameProfile { game, is_sg2, album_records, has_alpha: version >= ALPHA_VERSION, tile_sizes }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{ColourExpansion, PixelFormat, TRANSPARENT_KEY_COLOUR};
+
 /// A trait for providing [ImageBuilder] for a new image.
 ///
 /// Gets called internally to create a new builder for each encountered image.
@@ -9,6 +11,16 @@ pub trait ImageBuilderFactory<T> {
 
     /// Create a new builder for an image of the provided size
     fn new_builder(&self, width: u16, height: u16) -> Self::Builder;
+
+    /// Format of the pixels passed to the builders, RGBA by default
+    fn pixel_format(&self) -> PixelFormat {
+        PixelFormat::Rgba8
+    }
+
+    /// How colour channels and alpha are expanded from 5 to 8 bits, by shifting by default
+    fn colour_expansion(&self) -> ColourExpansion {
+        ColourExpansion::Shift
+    }
 }
 
 /// A trait for building an image from provided pixels.
@@ -17,10 +29,21 @@ pub trait ImageBuilderFactory<T> {
 ///
 /// See [VecImageBuilder] for basic implementation that creates a vector of bytes representing raw pixel data.
 pub trait ImageBuilder<T> {
-    /// Set the specified pixel to the given colour given as RGBA
+    /// Set the specified pixel to the given colour given as RGBA (or the 4 byte format requested by the factory)
     fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]);
 
-    /// Set alpha on the specified pixel
+    /// Set the specified pixel to the given 16 bit colour, used instead of `set_pixel_by_pos` for the packed formats
+    ///
+    /// By default the colour is passed on to `set_pixel_by_pos` as its little endian bytes followed by zeroes.
+    fn set_raw_pixel_by_pos(&mut self, position: usize, colour: u16) {
+        let [low, high] = colour.to_le_bytes();
+        self.set_pixel_by_pos(position, [low, high, 0, 0]);
+    }
+
+    /// Called for pixels left transparent because they have the key colour (see [`crate::DecodeOptions::key_colour`]), does nothing by default
+    fn set_keyed_pixel(&mut self, _position: usize, _colour: u16) {}
+
+    /// Set alpha on the specified pixel, not used for premultiplied formats
     fn set_alpha(&mut self, position: usize, alpha: u8);
 
     /// Mirror each pixel horizontally
@@ -37,10 +60,43 @@ impl ImageBuilderFactory<Vec<u8>> for VecImageBuilderFactory {
     type Builder = VecImageBuilder;
 
     fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
-        let width = width as usize;
-        let height = height as usize;
-        let pixels = vec![0; width * height * 4];
-        VecImageBuilder { width, height, pixels }
+        VecImageBuilder::new(width, height, PixelFormat::Rgba8)
+    }
+}
+
+/// Implementation of [ImageBuilderFactory] for creating images as vector of bytes in the given pixel format.
+///
+/// Packed formats use 2 bytes per pixel (little endian), the others 4.
+/// Packed formats have no alpha, so transparent pixels are left as [`TRANSPARENT_KEY_COLOUR`] instead of zeroes.
+pub struct FormattedVecImageBuilderFactory {
+    pub format: PixelFormat,
+    pub colour_expansion: ColourExpansion,
+}
+
+impl FormattedVecImageBuilderFactory {
+    pub fn new(format: PixelFormat) -> Self {
+        FormattedVecImageBuilderFactory { format, colour_expansion: ColourExpansion::Shift }
+    }
+
+    pub fn with_colour_expansion(mut self, colour_expansion: ColourExpansion) -> Self {
+        self.colour_expansion = colour_expansion;
+        self
+    }
+}
+
+impl ImageBuilderFactory<Vec<u8>> for FormattedVecImageBuilderFactory {
+    type Builder = VecImageBuilder;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        VecImageBuilder::new(width, height, self.format)
+    }
+
+    fn pixel_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn colour_expansion(&self) -> ColourExpansion {
+        self.colour_expansion
     }
 }
 
@@ -48,30 +104,55 @@ impl ImageBuilderFactory<Vec<u8>> for VecImageBuilderFactory {
 pub struct VecImageBuilder {
     width: usize,
     height: usize,
+    bytes_per_pixel: usize,
     pixels: Vec<u8>,
 }
 
+impl VecImageBuilder {
+    fn new(width: u16, height: u16, format: PixelFormat) -> Self {
+        let width = width as usize;
+        let height = height as usize;
+        let bytes_per_pixel = format.bytes_per_pixel();
+
+        let pixels = if format.is_packed() {
+            TRANSPARENT_KEY_COLOUR.to_le_bytes().repeat(width * height)
+        } else {
+            vec![0; width * height * bytes_per_pixel]
+        };
+
+        VecImageBuilder { width, height, bytes_per_pixel, pixels }
+    }
+}
+
 impl ImageBuilder<Vec<u8>> for VecImageBuilder {
     fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
-        let i = position * 4;
-        self.pixels[i..(i + 4)].clone_from_slice(&data);
+        let i = position * self.bytes_per_pixel;
+        self.pixels[i..(i + self.bytes_per_pixel)].clone_from_slice(&data[..self.bytes_per_pixel]);
+    }
+
+    fn set_raw_pixel_by_pos(&mut self, position: usize, colour: u16) {
+        let i = position * self.bytes_per_pixel;
+        self.pixels[i..(i + 2)].clone_from_slice(&colour.to_le_bytes());
     }
 
     fn set_alpha(&mut self, position: usize, alpha: u8) {
-        let i = position * 4 + 3;
-        self.pixels[i] = alpha;
+        // Packed formats have no room for alpha
+        if self.bytes_per_pixel == 4 {
+            let i = position * 4 + 3;
+            self.pixels[i] = alpha;
+        }
     }
 
     fn flip_horizontal(&mut self) {
+        let bytes_per_pixel = self.bytes_per_pixel;
         let mut row_offset = 0;
         for _y in 0..self.height {
             for x in 0..(self.width / 2) {
-                let pixel_a = (row_offset + x) * 4;
-                let pixel_b = (row_offset + self.width - x - 1) * 4;
-                self.pixels.swap(pixel_a, pixel_b);
-                self.pixels.swap(pixel_a + 1, pixel_b + 1);
-                self.pixels.swap(pixel_a + 2, pixel_b + 2);
-                self.pixels.swap(pixel_a + 3, pixel_b + 3);
+                let pixel_a = (row_offset + x) * bytes_per_pixel;
+                let pixel_b = (row_offset + self.width - x - 1) * bytes_per_pixel;
+                for i in 0..bytes_per_pixel {
+                    self.pixels.swap(pixel_a + i, pixel_b + i);
+                }
             }
             row_offset += self.width;
         }
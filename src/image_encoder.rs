@@ -0,0 +1,218 @@
+use crate::{ImageType, Result, SgFile, SgImageError, SgImageMetadata, TRANSPARENT_KEY_COLOUR, TileSize};
+
+const MAX_SKIP: usize = 255;
+const MAX_FILL: usize = 254;
+
+impl SgImageMetadata {
+    /// Encode RGBA pixel data into the raw format used by this image's type.
+    ///
+    /// Pixels are expected in the same layout as produced by [`crate::VecImageBuilderFactory`].
+    /// Fully transparent pixels are either skipped or stored using the transparent colour key.
+    /// Partially transparent pixels get an alpha mask when `include_alpha` is set (only supported by files with alpha, version `0xd6`).
+    ///
+    /// Updates `length`, `uncompressed_length` (isometric images only), `alpha_offset` and `alpha_length` to match the encoded data.
+    /// The returned bytes can be passed to [`crate::SgFile::write_to_path`].
+    ///
+    /// The footprint of isometric images is laid out using `tile_sizes`, see [`SgImageMetadata::isometric_geometry`].
+    pub fn encode_image(&mut self, pixels: &[u8], include_alpha: bool, tile_sizes: &[TileSize]) -> Result<Vec<u8>> {
+        let pixel_count = self.width as usize * self.height as usize;
+
+        if pixels.len() != pixel_count * 4 {
+            return Err(SgImageError::ImageDataLengthMismatch { expected: pixel_count as u64 * 4, actual: pixels.len() as u64 });
+        }
+
+        let mut data = Vec::new();
+
+        if pixel_count > 0 {
+            match self.kind() {
+                ImageType::Plain => Self::encode_plain_image(pixels, &mut data),
+                ImageType::Isometric => self.encode_isometric_image(pixels, tile_sizes, &mut data)?,
+                ImageType::Sprite | ImageType::Font => Self::encode_transparent_image(pixels, |_| false, &mut data),
+                ImageType::Unknown(image_type) => return Err(SgImageError::UnknownImageType(image_type)),
+            }
+        }
+
+        self.length = data.len() as u32;
+
+        let alpha_start = data.len();
+
+        if include_alpha {
+            Self::encode_alpha_mask(pixels, &mut data);
+        }
+
+        self.alpha_length = (data.len() - alpha_start) as u32;
+        self.alpha_offset = if self.has_alpha() { self.offset.saturating_add(self.length) } else { 0 };
+
+        Ok(data)
+    }
+
+    fn encode_plain_image(pixels: &[u8], data: &mut Vec<u8>) {
+        for pixel in pixels.chunks_exact(4) {
+            data.extend_from_slice(&Self::to_555_colour(pixel).to_le_bytes());
+        }
+    }
+
+    fn encode_isometric_image(&mut self, pixels: &[u8], tile_sizes: &[TileSize], data: &mut Vec<u8>) -> Result<()> {
+        let base_height = (self.width as u32 + 2) / 2;
+        self.uncompressed_length = (self.width as u32 + 2) * base_height;
+
+        let positions = self.isometric_base_positions(tile_sizes)?;
+        let mut is_base = vec![false; pixels.len() / 4];
+
+        for position in positions {
+            let pixel = pixels.get(position * 4..position * 4 + 4).ok_or(SgImageError::PixelOutOfBounds(position))?;
+            data.extend_from_slice(&Self::to_555_colour(pixel).to_le_bytes());
+            is_base[position] = true;
+        }
+
+        // The transparent part covers everything that is not a part of the base
+        Self::encode_transparent_image(pixels, |position| is_base[position], data);
+
+        Ok(())
+    }
+
+    fn encode_transparent_image(pixels: &[u8], is_skipped: impl Fn(usize) -> bool, data: &mut Vec<u8>) {
+        let is_skipped = |position: usize| pixels[position * 4 + 3] == 0 || is_skipped(position);
+
+        Self::encode_runs(pixels.len() / 4, is_skipped, data, |position, data| {
+            data.extend_from_slice(&Self::to_555_colour(&pixels[position * 4..position * 4 + 4]).to_le_bytes());
+        });
+    }
+
+    fn encode_alpha_mask(pixels: &[u8], data: &mut Vec<u8>) {
+        // Fully transparent pixels are not drawn and fully opaque ones are drawn without transparency already
+        let is_skipped = |position: usize| pixels[position * 4 + 3] == 0 || pixels[position * 4 + 3] == 0xff;
+
+        Self::encode_runs(pixels.len() / 4, is_skipped, data, |position, data| {
+            data.push(pixels[position * 4 + 3] >> 3);
+        });
+    }
+
+    /// Shared run length encoding, a run is either `255` followed by the number of pixels to skip,
+    /// or the number of pixels to fill in followed by their values.
+    fn encode_runs(pixel_count: usize, is_skipped: impl Fn(usize) -> bool, data: &mut Vec<u8>, mut write_value: impl FnMut(usize, &mut Vec<u8>)) {
+        // Skipping trailing pixels is not needed
+        let end = match (0..pixel_count).rev().find(|position| !is_skipped(*position)) {
+            Some(last) => last + 1,
+            None => return,
+        };
+
+        let mut position = 0;
+
+        while position < end {
+            let start = position;
+
+            if is_skipped(position) {
+                while position < end && position - start < MAX_SKIP && is_skipped(position) {
+                    position += 1;
+                }
+                data.push(255);
+                data.push((position - start) as u8);
+            } else {
+                while position < end && position - start < MAX_FILL && !is_skipped(position) {
+                    position += 1;
+                }
+                data.push((position - start) as u8);
+                for i in start..position {
+                    write_value(i, data);
+                }
+            }
+        }
+    }
+
+    fn to_555_colour(pixel: &[u8]) -> u16 {
+        if pixel[3] == 0 {
+            return TRANSPARENT_KEY_COLOUR;
+        }
+
+        let r = (pixel[0] >> 3) as u16;
+        let g = (pixel[1] >> 3) as u16;
+        let b = (pixel[2] >> 3) as u16;
+
+        (r << 10) | (g << 5) | b
+    }
+}
+
+impl SgFile {
+    /// Encode RGBA pixel data of the given image, with isometric footprints laid out using the tile sizes of this file.
+    ///
+    /// See [`SgImageMetadata::encode_image`].
+    pub fn encode_image(&self, image: &mut SgImageMetadata, pixels: &[u8], include_alpha: bool) -> Result<Vec<u8>> {
+        image.encode_image(pixels, include_alpha, &self.profile().tile_sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SgImageError, SgImageMetadata, VecImageBuilderFactory};
+
+    /// Opaque pixels with colours that survive the conversion to 555.
+    fn opaque_pixel(position: usize) -> [u8; 4] {
+        [(position * 8) as u8, (position * 16) as u8 & 0xf8, 0xf8 - (position * 8) as u8, 0xff]
+    }
+
+    fn round_trip(image: &mut SgImageMetadata, pixels: &[u8], include_alpha: bool) -> Vec<u8> {
+        let data = image.encode_image(pixels, include_alpha, &[]).unwrap();
+        image.load_image_from_raw_data(&data, &VecImageBuilderFactory).unwrap()
+    }
+
+    #[test]
+    fn plain_images_round_trip() {
+        let mut image = SgImageMetadata { width: 3, height: 2, ..Default::default() };
+        let pixels: Vec<u8> = (0..6).flat_map(opaque_pixel).collect();
+
+        assert_eq!(round_trip(&mut image, &pixels, false), pixels);
+        assert_eq!(image.length, 12);
+    }
+
+    #[test]
+    fn sprite_images_round_trip_with_alpha() {
+        let mut image = SgImageMetadata { width: 4, height: 3, image_type: 256, ..Default::default() };
+        let pixels: Vec<u8> = (0..12)
+            .flat_map(|position| match position % 3 {
+                0 => [0, 0, 0, 0],
+                1 => opaque_pixel(position),
+                _ => [0x40, 0x80, 0xc0, (position * 16) as u8],
+            })
+            .collect();
+
+        assert_eq!(round_trip(&mut image, &pixels, true), pixels);
+        assert!(image.has_alpha());
+    }
+
+    #[test]
+    fn long_runs_are_split() {
+        let mut image = SgImageMetadata { width: 600, height: 1, image_type: 276, ..Default::default() };
+        let pixels: Vec<u8> = (0..600).flat_map(|position| if position < 300 { [0; 4] } else { opaque_pixel(position % 31) }).collect();
+
+        assert_eq!(round_trip(&mut image, &pixels, false), pixels);
+        // Skips of 255 and 45 pixels, fills of 254 and 46 pixels
+        assert_eq!(image.length, 2 + 2 + 1 + 254 * 2 + 1 + 46 * 2);
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let mut image = SgImageMetadata { width: 2, height: 2, ..Default::default() };
+        let err = image.encode_image(&[0; 12], false, &[]).unwrap_err();
+        assert!(matches!(err, SgImageError::ImageDataLengthMismatch { expected: 16, actual: 12 }), "{err:?}");
+
+        image.image_type = 99;
+        let err = image.encode_image(&[0; 16], false, &[]).unwrap_err();
+        assert!(matches!(err, SgImageError::UnknownImageType(99)), "{err:?}");
+    }
+
+    #[test]
+    fn isometric_images_round_trip() {
+        // A single 58x30 tile, stored as 2 bytes for each of its 900 pixels
+        let mut image = SgImageMetadata { width: 58, height: 40, image_type: 30, uncompressed_length: 1800, ..Default::default() };
+        let base = image.isometric_base_positions(&crate::TileSize::DEFAULTS).unwrap();
+
+        // Base pixels plus a column of the top part
+        let pixels: Vec<u8> = (0..58 * 40)
+            .flat_map(|position| if base.contains(&position) || position % 58 == 29 { opaque_pixel(position % 31) } else { [0; 4] })
+            .collect();
+
+        assert_eq!(round_trip(&mut image, &pixels, false), pixels);
+        assert_eq!(image.uncompressed_length, 1800);
+    }
+}
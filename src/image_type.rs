@@ -0,0 +1,34 @@
+/// Category of an image, determining how its pixel data is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageType {
+    /// Uncompressed image, raw `image_type` of 0, 1, 10, 12 or 13
+    Plain,
+    /// Isometric tile, uncompressed base followed by a compressed top part, raw `image_type` of 30
+    Isometric,
+    /// Compressed image with transparent pixels skipped, raw `image_type` of 256 or 257
+    Sprite,
+    /// Compressed font character, raw `image_type` of 276
+    Font,
+    /// Image type not supported by this library
+    Unknown(u16),
+}
+
+impl From<u16> for ImageType {
+    fn from(value: u16) -> Self {
+        match value {
+            0 | 1 | 10 | 12 | 13 => ImageType::Plain,
+            30 => ImageType::Isometric,
+            256 | 257 => ImageType::Sprite,
+            276 => ImageType::Font,
+            _ => ImageType::Unknown(value),
+        }
+    }
+}
+
+impl ImageType {
+    /// Checks if all of the pixel data is compressed.
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, ImageType::Sprite | ImageType::Font)
+    }
+}
@@ -1,13 +1,16 @@
-//! A library for reading sg3 files used in some Impressions Games city building games (Cesar 3, Pharaoh, Zeus, Emperor etc.).
+//! A library for reading sg2 and sg3 files used in some Impressions Games city building games (Caesar 3, Pharaoh, Zeus, Emperor etc.).
 //!
 //! Documentation of the format can be found at https://github.com/bvschaik/citybuilding-tools/wiki/SG-file-format#image-data.
 //!
 //! Simple usage:
-//! ```rust
+//! ```rust,no_run
 //! use sg_image_reader::{SgFile, VecImageBuilderFactory};
 //!
+//! # fn main() -> sg_image_reader::Result<()> {
 //! let path = "path-to-file";
 //! let (sg_file, pixel_data): (SgFile, Vec<Vec<u8>>) = SgFile::load_fully(path, &VecImageBuilderFactory)?;
+//! # Ok(())
+//! # }
 //! ```
 //!
 //! The basic example provides a vector of raw bytes for all the images.
@@ -15,36 +18,79 @@
 //! It is also possible to construct the required images directly by implementing the [`ImageBuilderFactory`] trait and passing it instead of the [`VecImageBuilderFactory`].
 //!
 //! Pixel data can also be loaded for one image at a time, see `viewer` example for an example of that
-//! ```rust
+//! ```rust,no_run
 //! use std::fs::File;
 //! use std::io::BufReader;
 //! use sg_image_reader::{SgFile, VecImageBuilderFactory};
 //!
+//! # fn main() -> sg_image_reader::Result<()> {
+//! # let path = "path-to-file";
 //! // Load just the metadata
 //! let sg_file = SgFile::load_from_path(path)?;
 //!
-//! // Select the image we want to load pixel data for
-//! let image = &sg_file.images[11];
+//! // Select the image we want to load pixel data for, mirrored images are resolved to the image holding their data
+//! let image = sg_file.resolve_mirror(&sg_file.images[11])?;
 //!
 //! // Get the path of the file where that data is located
-//! let path = sg_file.get_555_file_path(image.album_id as usize, image.is_external());
+//! let path = sg_file.get_555_file_path(image.image.album_id as usize, image.image.is_external())?;
 //!
 //! // Create a new reader
 //! let mut buf_reader = BufReader::new(File::open(path)?);
 //!
 //! // Load pixel data for that specific image
 //! let pixel_data = image.load_image(&mut buf_reader, &VecImageBuilderFactory);
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! Files can also be written back, using the raw (still encoded) pixel data
+//! ```rust,no_run
+//! use sg_image_reader::SgFile;
+//!
+//! # fn main() -> sg_image_reader::Result<()> {
+//! # let (path, target_path) = ("path-to-file", "target-path");
+//! // Load metadata and raw pixel data
+//! let sg_file = SgFile::load_from_path(path)?;
+//! let raw_data = sg_file.load_raw_image_data()?;
+//!
+//! // Write the sg3 file along with its 555 files
+//! sg_file.write_to_path(target_path, &raw_data)?;
+//! # Ok(())
+//! # }
+//! ```
+pub use atlas::{Atlas, AtlasPacker, AtlasPage, AtlasRect};
+pub use decode_options::{DecodeOptions, TRANSPARENT_KEY_COLOUR};
 pub use error::{Result, SgImageError};
+pub use game::{Game, GameProfile};
 pub use image_builder::*;
-pub use sg_album::SgAlbum;
+pub use image_type::ImageType;
+pub use pixel_format::{ColourExpansion, PixelFormat};
+pub use resolver::{FileResolver, FolderResolver};
+#[cfg(feature = "image")]
+pub use rgba_image::{RgbaImageBuilder, RgbaImageBuilderFactory};
+pub use sg_album::{SgAlbum, SgAlbumView};
+pub use sg_animation::SgAnimation;
 pub use sg_file::SgFile;
-pub use sg_image_metadata::SgImageMetadata;
+pub use sg_image_metadata::{IsometricPart, ResolvedImage, SgImageMetadata};
+pub use sg_walker::{Direction, SgWalker, SgWalkerFrame, WALKER_DIRECTIONS};
+pub use tile_size::{IsometricGeometry, TileSize};
 pub(crate) use utils::*;
 
+mod atlas;
+mod decode_options;
 mod error;
+mod game;
 mod image_builder;
+mod image_encoder;
+mod image_type;
+mod pixel_format;
+mod resolver;
+#[cfg(feature = "image")]
+mod rgba_image;
 mod sg_album;
+mod sg_animation;
 mod sg_file;
 mod sg_image_metadata;
+mod sg_walker;
+mod tile_size;
 mod utils;
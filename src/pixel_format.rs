@@ -0,0 +1,180 @@
+use crate::{ImageBuilder, Result, SgImageError};
+
+/// Layout of the pixels passed to an [`ImageBuilder`], requested by its [`crate::ImageBuilderFactory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    /// Red, green, blue and alpha bytes
+    #[default]
+    Rgba8,
+    /// Blue, green, red and alpha bytes
+    Bgra8,
+    /// Red, green, blue and alpha bytes, with the colour multiplied by alpha
+    PremultipliedRgba8,
+    /// Blue, green, red and alpha bytes, with the colour multiplied by alpha
+    PremultipliedBgra8,
+    /// The 16 bit colour as stored in the file, 5 bits for each channel (red in the high bits)
+    ///
+    /// Packed formats have no alpha, transparent pixels are never set and should be initialised to [`crate::TRANSPARENT_KEY_COLOUR`].
+    Raw555,
+    /// 16 bit colour with 5 bits for red and blue and 6 bits for green (red in the high bits)
+    Rgb565,
+}
+
+/// How 5 bit channels (and alpha) are expanded to 8 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColourExpansion {
+    /// Shift the value into the high bits, fast but white becomes 248
+    #[default]
+    Shift,
+    /// Repeat the high bits of the value in the low bits, so the full range from 0 to 255 is used
+    Replicate,
+}
+
+impl ColourExpansion {
+    /// Expand the 5 bit value to 8 bits.
+    pub fn expand(&self, value: u8) -> u8 {
+        match self {
+            ColourExpansion::Shift => value << 3,
+            ColourExpansion::Replicate => {
+                let value = value & 0x1f;
+                (value << 3) | (value >> 2)
+            }
+        }
+    }
+}
+
+impl PixelFormat {
+    /// Checks if pixels are passed as 16 bit values to [`ImageBuilder::set_raw_pixel_by_pos`] instead of bytes.
+    pub fn is_packed(&self) -> bool {
+        matches!(self, PixelFormat::Raw555 | PixelFormat::Rgb565)
+    }
+
+    /// Checks if the alpha mask is applied to the colour before it is passed to the builder.
+    pub fn is_premultiplied(&self) -> bool {
+        matches!(self, PixelFormat::PremultipliedRgba8 | PixelFormat::PremultipliedBgra8)
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        if self.is_packed() { 2 } else { 4 }
+    }
+
+    fn convert_rgba(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        match self {
+            PixelFormat::Bgra8 => [b, g, r, a],
+            PixelFormat::PremultipliedRgba8 => [premultiply(r, a), premultiply(g, a), premultiply(b, a), a],
+            PixelFormat::PremultipliedBgra8 => [premultiply(b, a), premultiply(g, a), premultiply(r, a), a],
+            _ => [r, g, b, a],
+        }
+    }
+
+    fn convert_555(&self, colour: u16) -> u16 {
+        match self {
+            PixelFormat::Rgb565 => {
+                let green = (colour >> 5) & 0x1f;
+                ((colour & 0x7c00) << 1) | (((green << 1) | (green >> 4)) << 5) | (colour & 0x1f)
+            }
+            _ => colour,
+        }
+    }
+}
+
+fn premultiply(value: u8, alpha: u8) -> u8 {
+    ((value as u16 * alpha as u16 + 127) / 255) as u8
+}
+
+/// Converts decoded colours to the pixel format requested by the factory and passes them to the builder.
+pub(crate) struct PixelWriter<'a, B> {
+    pub image_builder: &'a mut B,
+    format: PixelFormat,
+    expansion: ColourExpansion,
+    key_colour: Option<u16>,
+    pixel_count: usize,
+    /// Alpha of each pixel, read ahead of the colours for premultiplied formats
+    alpha_mask: Option<Vec<u8>>,
+}
+
+impl<'a, B> PixelWriter<'a, B> {
+    pub fn new(
+        image_builder: &'a mut B,
+        format: PixelFormat,
+        expansion: ColourExpansion,
+        key_colour: Option<u16>,
+        pixel_count: usize,
+        alpha_mask: Option<Vec<u8>>,
+    ) -> Self {
+        PixelWriter { image_builder, format, expansion, key_colour, pixel_count, alpha_mask }
+    }
+
+    pub fn set_555_pixel<T>(&mut self, position: usize, colour: u16) -> Result<()>
+    where
+        B: ImageBuilder<T>,
+    {
+        self.check_position(position)?;
+
+        if self.key_colour == Some(colour) {
+            self.image_builder.set_keyed_pixel(position, colour);
+            return Ok(());
+        }
+
+        if self.format.is_packed() {
+            self.image_builder.set_raw_pixel_by_pos(position, self.format.convert_555(colour));
+            return Ok(());
+        }
+
+        let r = self.expansion.expand((colour >> 10) as u8 & 0x1f);
+        let g = self.expansion.expand((colour >> 5) as u8 & 0x1f);
+        let b = self.expansion.expand(colour as u8 & 0x1f);
+        let a = self.alpha_mask.as_ref().map_or(0xff, |alpha_mask| alpha_mask[position]);
+
+        self.image_builder.set_pixel_by_pos(position, self.format.convert_rgba([r, g, b, a]));
+
+        Ok(())
+    }
+
+    pub fn set_alpha<T>(&mut self, position: usize, alpha: u8) -> Result<()>
+    where
+        B: ImageBuilder<T>,
+    {
+        self.check_position(position)?;
+        self.image_builder.set_alpha(position, alpha);
+        Ok(())
+    }
+
+    fn check_position(&self, position: usize) -> Result<()> {
+        if position >= self.pixel_count {
+            return Err(SgImageError::PixelOutOfBounds(position));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ColourExpansion, FormattedVecImageBuilderFactory, PixelFormat, SgImageMetadata, TRANSPARENT_KEY_COLOUR};
+
+    #[test]
+    fn packed_formats_keep_transparent_pixels_keyed() {
+        let mut image = SgImageMetadata { width: 2, height: 1, image_type: 256, ..Default::default() };
+        let data = image.encode_image(&[0, 0, 0, 0, 0xf8, 0, 0, 0xff], false, &[]).unwrap();
+
+        let raw = image.load_image_from_raw_data(&data, &FormattedVecImageBuilderFactory::new(PixelFormat::Raw555)).unwrap();
+        let rgb565 = image.load_image_from_raw_data(&data, &FormattedVecImageBuilderFactory::new(PixelFormat::Rgb565)).unwrap();
+
+        assert_eq!(raw, [TRANSPARENT_KEY_COLOUR.to_le_bytes(), 0x7c00_u16.to_le_bytes()].concat());
+        assert_eq!(rgb565, [TRANSPARENT_KEY_COLOUR.to_le_bytes(), 0xf800_u16.to_le_bytes()].concat());
+    }
+
+    #[test]
+    fn replicated_expansion_uses_the_full_range() {
+        let mut image = SgImageMetadata { width: 2, height: 1, image_type: 256, ..Default::default() };
+        let data = image.encode_image(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x80], true, &[]).unwrap();
+
+        let shifted = FormattedVecImageBuilderFactory::new(PixelFormat::Rgba8);
+        let replicated = FormattedVecImageBuilderFactory::new(PixelFormat::Rgba8).with_colour_expansion(ColourExpansion::Replicate);
+
+        assert_eq!(image.load_image_from_raw_data(&data, &shifted).unwrap(), [0xf8, 0xf8, 0xf8, 0xff, 0xf8, 0xf8, 0xf8, 0x80]);
+        assert_eq!(image.load_image_from_raw_data(&data, &replicated).unwrap(), [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x84]);
+    }
+}
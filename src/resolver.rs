@@ -0,0 +1,167 @@
+use crate::{Result, SgImageError};
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// A trait for providing the files referenced by a sg file.
+///
+/// Used to load sg and 555 files from sources other than the filesystem (archives, asset stores etc.).
+///
+/// See [FolderResolver] for the default implementation that opens files from a folder.
+pub trait FileResolver {
+    /// The type of the returned readers
+    type Reader: Read + Seek;
+
+    /// Open the file with the given name (name of the sg file or of one of its 555 files)
+    fn open(&self, filename: &str) -> Result<Self::Reader>;
+
+    /// Path of the file with the given name, used when reporting errors
+    fn path(&self, filename: &str) -> PathBuf {
+        PathBuf::from(filename)
+    }
+
+    /// Open the file with the given name along with its path, for resolvers that find the path while opening the file
+    fn open_with_path(&self, filename: &str) -> Result<(Self::Reader, PathBuf)> {
+        Ok((self.open(filename)?, self.path(filename)))
+    }
+}
+
+/// Default implementation of [FileResolver] opening files from a list of folders.
+///
+/// Folders are searched in order, first for the exact filename and then (if `case_insensitive` is set) for a filename differing only in case.
+/// Game files copied from Windows often differ in case from the names stored in the sg files.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FolderResolver {
+    pub folders: Vec<PathBuf>,
+    pub case_insensitive: bool,
+}
+
+impl FolderResolver {
+    /// Create a case-insensitive resolver searching the given folder.
+    pub fn new<P: Into<PathBuf>>(folder: P) -> Self {
+        FolderResolver { folders: vec![folder.into()], case_insensitive: true }
+    }
+
+    /// Add a folder to search for files not found in the previous folders.
+    pub fn with_folder<P: Into<PathBuf>>(mut self, folder: P) -> Self {
+        self.folders.push(folder.into());
+        self
+    }
+
+    /// Add a sub-folder of the first folder to search for files not found in the previous folders.
+    ///
+    /// Caesar 3 keeps external 555 files in the `555` sub-folder, Pharaoh in the `Data` sub-folder.
+    pub fn with_sub_folder<P: AsRef<Path>>(self, sub_folder: P) -> Self {
+        let folder = self.folders.first().map(|folder| folder.join(sub_folder.as_ref())).unwrap_or_else(|| sub_folder.as_ref().to_path_buf());
+        self.with_folder(folder)
+    }
+
+    /// Set whether filenames differing only in case should be matched.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Paths at which the file with the given name is looked for, in order.
+    pub fn candidates(&self, filename: &str) -> Vec<PathBuf> {
+        self.folders.iter().map(|folder| folder.join(filename)).collect()
+    }
+
+    /// Find the file with the given name in the search folders.
+    pub fn find(&self, filename: &str) -> Option<PathBuf> {
+        self.folders.iter().find_map(|folder| self.find_in_folder(folder, filename))
+    }
+
+    fn find_in_folder(&self, folder: &Path, filename: &str) -> Option<PathBuf> {
+        let path = folder.join(filename);
+
+        if path.is_file() {
+            return Some(path);
+        }
+
+        if !self.case_insensitive {
+            return None;
+        }
+
+        // Only the last component is matched, the filename might contain a relative path
+        let (folder, filename) = match (path.parent(), path.file_name()?.to_str()) {
+            (Some(parent), Some(name)) => (parent.to_path_buf(), name),
+            _ => return None,
+        };
+
+        let found = fs::read_dir(folder)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_str().is_some_and(|name| name.eq_ignore_ascii_case(filename)));
+
+        found.map(|entry| entry.path()).filter(|path| path.is_file())
+    }
+}
+
+impl FileResolver for FolderResolver {
+    type Reader = File;
+
+    fn open(&self, filename: &str) -> Result<Self::Reader> {
+        Ok(self.open_with_path(filename)?.0)
+    }
+
+    fn path(&self, filename: &str) -> PathBuf {
+        self.find(filename).or_else(|| self.candidates(filename).into_iter().next()).unwrap_or_else(|| PathBuf::from(filename))
+    }
+
+    fn open_with_path(&self, filename: &str) -> Result<(Self::Reader, PathBuf)> {
+        match self.find(filename) {
+            Some(path) => Ok((File::open(&path)?, path)),
+            None => Err(SgImageError::FileNotFound { filename: String::from(filename), candidates: self.candidates(filename) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty folder in the temporary directory, unique to the calling test.
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("sg_image_reader_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn filenames_differing_in_case_are_found() {
+        let folder = temp_folder("resolver_case");
+        fs::write(folder.join("ZEUS_General.555"), [1, 2]).unwrap();
+
+        let resolver = FolderResolver::new(&folder);
+        let (mut file, path) = resolver.open_with_path("Zeus_General.555").unwrap();
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(data, [1, 2]);
+        assert_eq!(path, folder.join("ZEUS_General.555"));
+        assert_eq!(resolver.path("Zeus_General.555"), path);
+
+        let err = resolver.with_case_insensitive(false).open("Zeus_General.555").unwrap_err();
+        assert!(matches!(err, SgImageError::FileNotFound { .. }), "{err:?}");
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn missing_files_list_every_candidate() {
+        let folder = temp_folder("resolver_missing");
+        let resolver = FolderResolver::new(&folder).with_sub_folder("555").with_folder("other");
+
+        let Err(SgImageError::FileNotFound { filename, candidates }) = resolver.open("Missing.555") else {
+            panic!("missing file was found");
+        };
+
+        assert_eq!(filename, "Missing.555");
+        assert_eq!(candidates, [folder.join("Missing.555"), folder.join("555").join("Missing.555"), PathBuf::from("other").join("Missing.555")]);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{ImageBuilder, ImageBuilderFactory, Result, SgFile, VecImageBuilder, VecImageBuilderFactory};
+use image::{DynamicImage, RgbaImage};
+use std::path::Path;
+
+/// Implementation of [ImageBuilderFactory] for creating images of the [image](https://crates.io/crates/image) crate.
+///
+/// Can build both [RgbaImage] and [DynamicImage].
+pub struct RgbaImageBuilderFactory;
+
+impl ImageBuilderFactory<RgbaImage> for RgbaImageBuilderFactory {
+    type Builder = RgbaImageBuilder;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        RgbaImageBuilder { width: width as u32, height: height as u32, builder: VecImageBuilderFactory.new_builder(width, height) }
+    }
+}
+
+impl ImageBuilderFactory<DynamicImage> for RgbaImageBuilderFactory {
+    type Builder = RgbaImageBuilder;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        ImageBuilderFactory::<RgbaImage>::new_builder(self, width, height)
+    }
+}
+
+/// Implementation of [ImageBuilder] for creating images of the [image](https://crates.io/crates/image) crate.
+pub struct RgbaImageBuilder {
+    width: u32,
+    height: u32,
+    builder: VecImageBuilder,
+}
+
+impl ImageBuilder<RgbaImage> for RgbaImageBuilder {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.builder.set_pixel_by_pos(position, data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        self.builder.set_alpha(position, alpha);
+    }
+
+    fn flip_horizontal(&mut self) {
+        self.builder.flip_horizontal();
+    }
+
+    fn build(self) -> RgbaImage {
+        // The buffer always matches the dimensions
+        RgbaImage::from_raw(self.width, self.height, self.builder.build()).expect("pixel buffer does not match image dimensions")
+    }
+}
+
+impl ImageBuilder<DynamicImage> for RgbaImageBuilder {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.builder.set_pixel_by_pos(position, data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        self.builder.set_alpha(position, alpha);
+    }
+
+    fn flip_horizontal(&mut self) {
+        self.builder.flip_horizontal();
+    }
+
+    fn build(self) -> DynamicImage {
+        DynamicImage::ImageRgba8(ImageBuilder::<RgbaImage>::build(self))
+    }
+}
+
+impl SgFile {
+    /// Load metadata and pixel data, with the images built as [RgbaImage].
+    pub fn load_fully_as_images<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<RgbaImage>)> {
+        Self::load_fully(path, &RgbaImageBuilderFactory)
+    }
+}
@@ -1,6 +1,9 @@
 use crate::Result;
-use crate::utils::ReadHelper;
-use std::io::{BufReader, Read, Seek};
+use crate::utils::{ReadHelper, WriteHelper};
+use crate::{SgFile, SgImageMetadata};
+use std::io::{BufReader, Read, Seek, Write};
+use std::ops::Range;
+use std::path::Path;
 use std::string::String;
 
 /// Metadata of an album.
@@ -9,6 +12,7 @@ use std::string::String;
 ///
 /// Some bytes from the metadata are of unknown meaning.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SgAlbum {
     pub id: u32,
     pub external_filename: String,
@@ -31,7 +35,27 @@ pub struct SgAlbum {
     pub unknown_e: [u8; 24],     // 24 unknown bytes
 }
 
+/// Images of a single album.
+///
+/// Images are indexed relative to the album, starting from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SgAlbumView<'a> {
+    pub album: &'a SgAlbum,
+    file: &'a SgFile,
+    indices: Range<usize>,
+}
+
 impl SgAlbum {
+    /// Name of the album, its external filename without the extension.
+    pub fn name(&self) -> &str {
+        Path::new(&self.external_filename).file_stem().and_then(|stem| stem.to_str()).unwrap_or(&self.external_filename)
+    }
+
+    /// Checks if any of the images of this album have their data in the 555 file of the sg file itself.
+    pub fn has_internal_images(&self) -> bool {
+        self.file_size_555 != 0
+    }
+
     pub(crate) fn load<R: Read + Seek>(reader: &mut BufReader<R>, id: u32) -> Result<SgAlbum> {
         let external_filename = reader.read_string(65)?;
         let comment = reader.read_string(51)?;
@@ -74,4 +98,107 @@ impl SgAlbum {
             unknown_e,
         })
     }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_string(&self.external_filename, 65)?;
+        writer.write_string(&self.comment, 51)?;
+        writer.write_u32_le(self.width)?;
+        writer.write_u32_le(self.height)?;
+        writer.write_u32_le(self.num_images)?;
+        writer.write_u32_le(self.start_index)?;
+        writer.write_u32_le(self.end_index)?;
+        writer.write_u32_le(self.image_id)?;
+        writer.write_u32_le(self.unknown_a)?;
+        writer.write_u32_le(self.unknown_b)?;
+        writer.write_u32_le(self.unknown_c)?;
+        writer.write_u32_le(self.unknown_d)?;
+        writer.write_u32_le(self.image_width)?;
+        writer.write_u32_le(self.image_height)?;
+        writer.write_u32_le(self.file_size_555)?;
+        writer.write_u32_le(self.total_file_size)?;
+        writer.write_u32_le(self.file_size_external)?;
+        writer.write_all(&self.unknown_e)?;
+        Ok(())
+    }
+}
+
+impl<'a> SgAlbumView<'a> {
+    /// Image with the given index within the album.
+    pub fn image(&self, index: usize) -> Option<&'a SgImageMetadata> {
+        self.file.images.get(self.image_index(index)?)
+    }
+
+    /// Index into [`SgFile::images`] of the image with the given index within the album.
+    pub fn image_index(&self, index: usize) -> Option<usize> {
+        self.indices.clone().nth(index)
+    }
+
+    /// All the images of the album, in order.
+    pub fn images(&self) -> impl Iterator<Item = &'a SgImageMetadata> + '_ {
+        self.file.images[self.indices.clone()].iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl SgFile {
+    /// Images of the album with the given id, from its `start_index` to its `end_index` (inclusive).
+    pub fn album(&self, id: usize) -> Option<SgAlbumView<'_>> {
+        let album = self.albums.get(id)?;
+
+        // The dummy record at index 0 does not belong to any album, indices past the last image are ignored
+        let start = (album.start_index as usize).max(1);
+        let end = (album.end_index as usize).saturating_add(1).min(self.images.len()).max(start);
+
+        Some(SgAlbumView { album, file: self, indices: start..end })
+    }
+
+    /// Images of the album with the given name (see [`SgAlbum::name`]), ignoring case.
+    pub fn album_by_name(&self, name: &str) -> Option<SgAlbumView<'_>> {
+        let id = self.albums.iter().position(|album| album.name().eq_ignore_ascii_case(name))?;
+        self.album(id)
+    }
+
+    /// Images of all the albums.
+    pub fn album_views(&self) -> impl Iterator<Item = SgAlbumView<'_>> + '_ {
+        (0..self.albums.len()).filter_map(|id| self.album(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SgAlbum, SgFile, SgImageMetadata};
+
+    fn album(external_filename: &str, start_index: u32, end_index: u32) -> SgAlbum {
+        SgAlbum { external_filename: String::from(external_filename), start_index, end_index, ..Default::default() }
+    }
+
+    #[test]
+    fn albums_cover_their_image_range() {
+        let sg_file = SgFile {
+            albums: vec![album("Zeus_System.bmp", 0, 2), album("Zeus_General.bmp", 3, 5), album("Empty.bmp", 6, 5), album("Broken.bmp", 5, 100)],
+            images: (0..7).map(|id| SgImageMetadata { id, ..Default::default() }).collect(),
+            ..Default::default()
+        };
+
+        let general = sg_file.album_by_name("zeus_general").unwrap();
+        assert_eq!(general.len(), 3);
+        assert_eq!(general.image(0).map(|image| image.id), Some(3));
+        assert_eq!(general.image(2).map(|image| image.id), Some(5));
+        assert_eq!(general.image(3), None);
+        assert_eq!(general.image_index(1), Some(4));
+        assert_eq!(general.images().map(|image| image.id).collect::<Vec<_>>(), [3, 4, 5]);
+
+        // Skips the dummy record, empty and oversized ranges are clamped
+        assert_eq!(sg_file.album(0).unwrap().images().map(|image| image.id).collect::<Vec<_>>(), [1, 2]);
+        assert!(sg_file.album(2).unwrap().is_empty());
+        assert_eq!(sg_file.album(3).unwrap().len(), 2);
+        assert!(sg_file.album_by_name("Zeus_Missing").is_none());
+    }
 }
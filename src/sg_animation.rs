@@ -0,0 +1,88 @@
+use crate::{SgFile, SgImageMetadata};
+use std::ops::Range;
+
+/// Animation made up of consecutive images of a sg file.
+///
+/// The base image has a non-zero `anim_sprites` and is followed by the frames of the animation.
+/// Frames are drawn on top of the base image, shifted by `x_offset` and `y_offset`.
+///
+/// There is no direction count, the image records do not store one and every animation found this way faces a single direction.
+/// Walkers store the frames of all their directions interleaved without any animation fields, use [`crate::SgWalker`] for those
+/// (always [`crate::WALKER_DIRECTIONS`] directions).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SgAnimation {
+    /// Index of the base image
+    pub base_image: usize,
+    /// Indices of the frame images
+    pub frames: Range<usize>,
+    /// Speed of the animation as stored in the file
+    pub speed_id: u8,
+    /// Whether the animation plays back and forth instead of restarting from the first frame
+    pub is_reversible: bool,
+    pub x_offset: u16,
+    pub y_offset: u16,
+}
+
+impl SgAnimation {
+    /// Create the animation starting at the given image, if it has any frames.
+    pub fn new(base_image: usize, image: &SgImageMetadata) -> Option<Self> {
+        if image.anim_sprites == 0 {
+            return None;
+        }
+
+        let start = base_image + 1;
+
+        Some(SgAnimation {
+            base_image,
+            frames: start..start + image.anim_sprites as usize,
+            speed_id: image.anim_speed_id,
+            is_reversible: image.is_reversible != 0,
+            x_offset: image.x_offset,
+            y_offset: image.y_offset,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Index of the image for the given frame of the animation.
+    pub fn frame(&self, frame: usize) -> Option<usize> {
+        self.frames.clone().nth(frame)
+    }
+
+    /// Index of the image shown at the given step of the animation, wrapping around (or playing backwards for reversible animations).
+    pub fn frame_at_step(&self, step: usize) -> usize {
+        let count = self.frame_count();
+
+        let frame = if self.is_reversible && count > 1 {
+            let position = step % (2 * count - 2);
+            if position < count { position } else { 2 * count - 2 - position }
+        } else {
+            step % count.max(1)
+        };
+
+        self.frames.start + frame
+    }
+}
+
+impl SgFile {
+    /// All the animations described by the images of this file.
+    ///
+    /// Animations with frames past the last image are skipped.
+    pub fn animations(&self) -> impl Iterator<Item = SgAnimation> + '_ {
+        (0..self.images.len()).filter_map(|index| self.animation(index))
+    }
+
+    /// Animation starting at the image with the given index, if there is one.
+    pub fn animation(&self, base_image: usize) -> Option<SgAnimation> {
+        let animation = SgAnimation::new(base_image, self.images.get(base_image)?)?;
+
+        if animation.frames.end > self.images.len() {
+            return None;
+        }
+
+        Some(animation)
+    }
+}
@@ -1,11 +1,31 @@
 use crate::ReadHelper;
+use crate::game::SUPPORTED_VERSIONS;
 use crate::Result;
 use crate::*;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::fmt::Debug;
+use std::fs;
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+const HEADER_UNKNOWN_LENGTH: usize = 640;
+const ALBUM_RECORD_LENGTH: usize = 200;
+const SG2_FILE_SIZE: u32 = 74480;
+const SG2_ENEMY_FILE_SIZE: u32 = 522680;
+const SG2_EXTERNAL_FOLDER: &str = "555";
+
+/// Minimum number of images decoded by a single parallel job, so that readers are not reopened too often.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_IMAGES: usize = 64;
+
+/// Reader of a 555 file along with its path and the album it belongs to (`None` for the internal file).
+type CachedReader<R> = (Option<u8>, PathBuf, BufReader<R>);
+
 /// Metadata of a sg file.
 ///
 /// Contains metadata of the images retrieved from the sg file.
@@ -13,6 +33,7 @@ use std::path::{Path, PathBuf};
 ///
 /// Some bytes from the metadata are of unknown meaning.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SgFile {
     pub folder: String,
     pub filename: String,
@@ -24,8 +45,15 @@ pub struct SgFile {
     pub total_file_size: u32,
     pub file_size_555: u32,
     pub file_size_external: u32,
+    pub unknown_header: Vec<u8>, // 640 bytes of unknown purpose
     pub albums: Vec<SgAlbum>,
     pub images: Vec<SgImageMetadata>,
+    /// Game detected when loading the file
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub game: Game,
+    /// Tile sizes tried for isometric images that do not store their size in tiles, the ones of the game are used if empty
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tile_sizes: Vec<TileSize>,
 }
 
 impl SgFile {
@@ -34,6 +62,10 @@ impl SgFile {
         let file_size = reader.read_u32_le()?;
         let version = reader.read_u32_le()?;
 
+        if !SUPPORTED_VERSIONS.contains(&version) {
+            return Err(SgImageError::UnsupportedVersion(version));
+        }
+
         let unknown = reader.read_u32_le()?;
         let max_image_count = reader.read_u32_le()?;
         let image_count = reader.read_u32_le()?;
@@ -43,19 +75,26 @@ impl SgFile {
         let file_size_555 = reader.read_u32_le()?;
         let file_size_external = reader.read_u32_le()?;
 
-        let max_album_records: u32 = if version == 0xd3 { 100 } else { 200 };
+        let profile = GameProfile::new(Game::from_version(version), version);
+        let max_album_records = profile.album_records;
+
+        if albums_count as usize > max_album_records {
+            return Err(SgImageError::TooManyAlbums(albums_count as usize));
+        }
 
-        reader.seek_relative(640)?;
+        let unknown_header = reader.read_vec(HEADER_UNKNOWN_LENGTH)?;
 
         let albums = Self::load_albums(reader, albums_count)?;
 
-        reader.seek_relative(200 * (max_album_records - albums_count) as i64)?;
+        reader.seek_relative((ALBUM_RECORD_LENGTH * (max_album_records - albums_count as usize)) as i64)?;
 
-        let images = Self::load_images_metadata(reader, image_count, version >= 0xd6)?;
+        let images = Self::load_images_metadata(reader, image_count, profile.has_alpha)?;
 
         reader.seek(SeekFrom::End(0))?;
 
-        Self::validate_header(&version, &file_size, &reader.stream_position()?)?;
+        Self::validate_header(&profile, file_size, reader.stream_position()?)?;
+
+        let game = Game::detect(version, &filename, &albums);
 
         let sg_file = SgFile {
             folder,
@@ -68,8 +107,11 @@ impl SgFile {
             total_file_size,
             file_size_555,
             file_size_external,
+            unknown_header,
             albums,
             images,
+            game,
+            tile_sizes: GameProfile::new(game, version).tile_sizes,
         };
 
         Ok(sg_file)
@@ -77,57 +119,299 @@ impl SgFile {
 
     /// Load metadata from the file founds on the given path.
     pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path.as_ref())?;
-        let mut reader = BufReader::new(file);
+        let path = path.as_ref();
+        let invalid_path = || SgImageError::InvalidPath(path.to_path_buf());
+
+        let folder = String::from(path.parent().and_then(Path::to_str).ok_or_else(invalid_path)?);
+        let filename = String::from(path.file_name().and_then(OsStr::to_str).ok_or_else(invalid_path)?);
+
+        let load = || {
+            let file = File::open(path)?;
+            let mut reader = BufReader::new(file);
+
+            Self::load_from_reader(&mut reader, folder, filename)
+        };
+
+        load().map_err(|err| err.in_file(path))
+    }
+
+    /// Load metadata from the sg file with the given name, opened using the provided resolver.
+    ///
+    /// The `folder` of the result is left empty, pixel data should be loaded using the same resolver.
+    pub fn load_from_resolver<Res: FileResolver>(resolver: &Res, filename: &str) -> Result<Self> {
+        let load = || {
+            let mut reader = BufReader::new(resolver.open(filename)?);
 
-        let folder = String::from(path.as_ref().parent().unwrap().to_str().unwrap());
-        let filename = String::from(path.as_ref().file_name().unwrap().to_str().unwrap());
+            Self::load_from_reader(&mut reader, String::new(), String::from(filename))
+        };
 
-        Self::load_from_reader(&mut reader, folder, filename)
+        load().map_err(|err| err.in_file(&resolver.path(filename)))
     }
 
     /// Load metadata and pixel data.
     pub fn load_fully<P: AsRef<Path>, T, F: ImageBuilderFactory<T>>(path: P, image_builder_factory: &F) -> Result<(Self, Vec<T>)> {
+        Self::load_fully_with_options(path, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load metadata and pixel data, decoded using the given options.
+    pub fn load_fully_with_options<P: AsRef<Path>, T, F: ImageBuilderFactory<T>>(
+        path: P,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<(Self, Vec<T>)> {
         let sg_file = Self::load_from_path(path)?;
 
-        let images = sg_file.load_image_data(image_builder_factory)?;
+        let images = sg_file.load_image_data_with_options(image_builder_factory, options)?;
 
         Ok((sg_file, images))
     }
 
-    fn validate_header(version: &u32, file_size: &u32, actual_file_size: &u64) -> Result<()> {
-        // SG2 file: FILE_SIZE = 74480 or 522680 (depending on whether it's a "normal" sg2 or an enemy sg2
-        if version == &0xd3 && !(file_size == &74480 || file_size == &522680) {
-            return Err(SgImageError::InvalidHeader);
+    /// Load metadata and pixel data of the sg file with the given name, with all the files opened using the provided resolver.
+    pub fn load_fully_from_resolver<Res: FileResolver, T, F: ImageBuilderFactory<T>>(
+        resolver: &Res,
+        filename: &str,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<(Self, Vec<T>)> {
+        let sg_file = Self::load_from_resolver(resolver, filename)?;
+
+        let images = sg_file.load_image_data_with_resolver(resolver, image_builder_factory, options)?;
+
+        Ok((sg_file, images))
+    }
+
+    /// Load metadata and pixel data, decoding images in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn load_fully_parallel<P: AsRef<Path>, T: Send, F: ImageBuilderFactory<T> + Sync>(path: P, image_builder_factory: &F) -> Result<(Self, Vec<T>)> {
+        Self::load_fully_parallel_with_options(path, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load metadata and pixel data, decoding images in parallel using the given options.
+    #[cfg(feature = "parallel")]
+    pub fn load_fully_parallel_with_options<P: AsRef<Path>, T: Send, F: ImageBuilderFactory<T> + Sync>(
+        path: P,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<(Self, Vec<T>)> {
+        let sg_file = Self::load_from_path(path)?;
+
+        let images = sg_file.load_image_data_parallel_with_options(image_builder_factory, options)?;
+
+        Ok((sg_file, images))
+    }
+
+    /// Load metadata and pixel data, with a separate result for each of the images.
+    ///
+    /// Fails only if the metadata cannot be loaded, see [`SgFile::load_image_data_lenient`].
+    pub fn load_fully_lenient<P: AsRef<Path>, T, F: ImageBuilderFactory<T>>(path: P, image_builder_factory: &F) -> Result<(Self, Vec<Result<T>>)> {
+        Self::load_fully_lenient_with_options(path, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load metadata and pixel data decoded using the given options, with a separate result for each of the images.
+    pub fn load_fully_lenient_with_options<P: AsRef<Path>, T, F: ImageBuilderFactory<T>>(
+        path: P,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<(Self, Vec<Result<T>>)> {
+        let sg_file = Self::load_from_path(path)?;
+
+        let images = sg_file.load_image_data_lenient_with_options(image_builder_factory, options);
+
+        Ok((sg_file, images))
+    }
+
+    /// Write metadata in the sg format to the provided writer.
+    ///
+    /// Image and album counts are taken from `images` and `albums`, all other header fields are written as they are.
+    /// Unused album and image records are filled with zeroes, more images than `max_image_count` fail with [`SgImageError::TooManyImages`].
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let profile = self.profile();
+        let max_album_records = profile.album_records;
+        let include_alpha = profile.has_alpha;
+
+        if self.albums.len() > max_album_records {
+            return Err(SgImageError::TooManyAlbums(self.albums.len()));
+        }
+
+        // Records past the last image are padded with zeroes, the file would not match its size with more images
+        let image_records = self.images.len().max(1);
+
+        if image_records > self.max_image_count as usize {
+            return Err(SgImageError::TooManyImages(image_records));
         }
 
-        // SG3 file: FILE_SIZE = the actual size of the sg3 file
-        if (version == &0xd5 || version == &0xd6) && !(file_size == &74480 || actual_file_size == &(*file_size as u64)) {
+        if self.unknown_header.len() > HEADER_UNKNOWN_LENGTH {
             return Err(SgImageError::InvalidHeader);
         }
 
+        writer.write_u32_le(self.file_size)?;
+        writer.write_u32_le(self.version)?;
+        writer.write_u32_le(self.unknown)?;
+        writer.write_u32_le(self.max_image_count)?;
+        writer.write_u32_le(self.images.len().saturating_sub(1) as u32)?;
+        writer.write_u32_le(self.albums.len() as u32)?;
+        writer.write_u32_le(self.album_records_without_system)?;
+        writer.write_u32_le(self.total_file_size)?;
+        writer.write_u32_le(self.file_size_555)?;
+        writer.write_u32_le(self.file_size_external)?;
+        writer.write_all(&self.unknown_header)?;
+        writer.write_zeroes(HEADER_UNKNOWN_LENGTH - self.unknown_header.len())?;
+
+        for album in &self.albums {
+            album.write(writer)?;
+        }
+
+        writer.write_zeroes(ALBUM_RECORD_LENGTH * (max_album_records - self.albums.len()))?;
+
+        // There is always at least the dummy record at index 0
+        if self.images.is_empty() {
+            SgImageMetadata::default().write(writer, include_alpha)?;
+        }
+
+        for image in &self.images {
+            image.write(writer, include_alpha)?;
+        }
+
+        let record_length = if include_alpha { 72 } else { 64 };
+        writer.write_zeroes((self.max_image_count as usize - image_records) * record_length)?;
+
+        Ok(())
+    }
+
+    /// Write metadata and pixel data to the given path.
+    ///
+    /// Pixel data is expected in the raw (encoded) form, as returned by [`SgFile::load_raw_image_data`], one entry per image.
+    /// It gets written into 555 files placed next to the sg file, data of mirrored and empty images is ignored.
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P, image_data: &[Vec<u8>]) -> Result<()> {
+        let path = path.as_ref();
+        let files_555 = self.build_555_files(image_data)?;
+
+        let write = || {
+            let mut writer = BufWriter::new(File::create(path)?);
+            self.write_to(&mut writer)?;
+            writer.flush()?;
+            Ok(())
+        };
+
+        write().map_err(|err: SgImageError| err.in_file(path))?;
+
+        for (album_id, data) in files_555 {
+            let path_555 = match album_id {
+                Some(album_id) => path.with_file_name(Self::to_555_filename(&self.albums[album_id].external_filename)),
+                None => path.with_extension("555"),
+            };
+
+            fs::write(&path_555, data).map_err(|err| SgImageError::from(err).in_file(&path_555))?;
+        }
+
+        Ok(())
+    }
+
+    /// Load raw (encoded) pixel data for all the images.
+    ///
+    /// The result can be passed to [`SgFile::write_to_path`].
+    pub fn load_raw_image_data(&self) -> Result<Vec<Vec<u8>>> {
+        self.map_images(&self.folder_resolver(), &DecodeOptions::default(), |resolved, reader, _| resolved.image.load_raw_data(reader)).collect()
+    }
+
+    fn build_555_files(&self, image_data: &[Vec<u8>]) -> Result<BTreeMap<Option<usize>, Vec<u8>>> {
+        if image_data.len() != self.images.len() {
+            return Err(SgImageError::ImageDataLengthMismatch { expected: self.images.len() as u64, actual: image_data.len() as u64 });
+        }
+
+        let mut files = BTreeMap::new();
+
+        // The game always expects the 555 file of the sg file itself to be present
+        files.insert(None, Vec::new());
+
+        for (image, data) in self.images.iter().zip(image_data) {
+            if image.is_mirrored() || image.length == 0 {
+                continue;
+            }
+
+            let expected = image.length as u64 + image.alpha_length as u64;
+
+            if data.len() as u64 != expected {
+                let err = SgImageError::ImageDataLengthMismatch { expected, actual: data.len() as u64 };
+                return Err(err.in_image(image.id, image.album_id, image.data_offset()));
+            }
+
+            let album_id = if image.is_external() {
+                if image.album_id as usize >= self.albums.len() {
+                    let err = SgImageError::UnknownAlbum(image.album_id as usize);
+                    return Err(err.in_image(image.id, image.album_id, image.data_offset()));
+                }
+                Some(image.album_id as usize)
+            } else {
+                None
+            };
+
+            let file: &mut Vec<u8> = files.entry(album_id).or_default();
+            let start = image.data_offset() as usize;
+            let end = start + data.len();
+
+            if file.len() < end {
+                file.resize(end, 0);
+            }
+
+            file[start..end].copy_from_slice(data);
+        }
+
+        Ok(files)
+    }
+
+    /// Checks if this is a Caesar 3 sg2 file.
+    pub fn is_sg2(&self) -> bool {
+        self.profile().is_sg2
+    }
+
+    /// Checks if this is a Caesar 3 enemy sg2 file (graphics of one of the enemy armies).
+    pub fn is_enemy_sg2(&self) -> bool {
+        self.is_sg2() && self.file_size == SG2_ENEMY_FILE_SIZE
+    }
+
+    /// Format settings for the game and version of this file.
+    pub fn profile(&self) -> GameProfile {
+        let mut profile = GameProfile::new(self.game, self.version);
+
+        if !self.tile_sizes.is_empty() {
+            profile.tile_sizes = self.tile_sizes.clone();
+        }
+
+        profile
+    }
+
+    fn validate_header(profile: &GameProfile, file_size: u32, actual_file_size: u64) -> Result<()> {
+        if profile.is_sg2 {
+            // SG2 file: FILE_SIZE = 74480 or 522680 (depending on whether it's a "normal" sg2 or an enemy sg2
+            if !(file_size == SG2_FILE_SIZE || file_size == SG2_ENEMY_FILE_SIZE) {
+                return Err(SgImageError::InvalidHeader);
+            }
+        } else if !(file_size == SG2_FILE_SIZE || actual_file_size == file_size as u64) {
+            // SG3 file: FILE_SIZE = the actual size of the sg3 file
+            return Err(SgImageError::FileSizeMismatch { expected: file_size as u64, actual: actual_file_size });
+        }
+
         Ok(())
     }
 
     fn load_albums<R: Read + Seek>(reader: &mut BufReader<R>, albums_count: u32) -> Result<Vec<SgAlbum>> {
-        let mut albums = Vec::with_capacity(albums_count as usize);
+        let mut albums = Vec::new();
         for i in 0..albums_count {
-            albums.push(SgAlbum::load(reader, i)?);
+            albums.push(SgAlbum::load(reader, i).map_err(|err| err.in_album(i))?);
         }
         Ok(albums)
     }
 
     fn load_images_metadata<R: Read + Seek>(file: &mut BufReader<R>, image_records: u32, alpha: bool) -> Result<Vec<SgImageMetadata>> {
-        let mut images: Vec<SgImageMetadata> = Vec::with_capacity(image_records as usize);
+        let mut images: Vec<SgImageMetadata> = Vec::new();
 
-        for i in 0..(image_records + 1) {
-            let mut image = SgImageMetadata::load(file, i, alpha)?;
+        for i in 0..=image_records {
+            let offset = file.stream_position()?;
+            let image = SgImageMetadata::load(file, i, alpha).map_err(|err| err.in_image(i, 0, offset))?;
 
-            let invert_offset = image.invert_offset;
-            if invert_offset != 0 {
-                image = images[(i as i32 + invert_offset) as usize].clone();
-                image.id = i;
-                image.invert_offset = invert_offset;
+            if image.is_mirrored() && Self::mirror_source_index(i as usize, image.invert_offset).is_none() {
+                return Err(SgImageError::InvalidInvertOffset(i));
             }
 
             images.push(image);
@@ -136,44 +420,503 @@ impl SgFile {
         Ok(images)
     }
 
-    fn load_image_data<T, F: ImageBuilderFactory<T>>(&self, image_factory_builder: &F) -> Result<Vec<T>> {
-        if self.images.is_empty() {
-            return Ok(Vec::new());
+    /// Index of the image mirrored by the image at the given index, which has to precede it.
+    fn mirror_source_index(index: usize, invert_offset: i32) -> Option<usize> {
+        let source = usize::try_from(index as i64 + invert_offset as i64).ok()?;
+        (source < index).then_some(source)
+    }
+
+    /// Record used to decode pixel data of the given image.
+    ///
+    /// Records of mirrored images are kept as they are stored, their pixel data is that of the image they mirror (following chains of mirrors).
+    /// For those the result is the record of that image, flagged to be flipped when decoded.
+    pub fn resolve_mirror<'a>(&'a self, image: &'a SgImageMetadata) -> Result<ResolvedImage<'a>> {
+        let mut source = image;
+
+        while source.is_mirrored() {
+            let index = Self::mirror_source_index(source.id as usize, source.invert_offset);
+            source = index.and_then(|index| self.images.get(index)).ok_or(SgImageError::InvalidInvertOffset(source.id))?;
         }
 
-        let mut result = Vec::new();
-        let mut last_file_params = (0, false);
-        let path = self.get_555_file_path(0, false);
-        let mut reader = BufReader::new(File::open(path)?);
+        Ok(ResolvedImage { image: source, is_mirrored: image.is_mirrored() })
+    }
 
-        for i in 0..self.images.len() {
-            let image = &self.images[i];
-            let file_params = (image.album_id as usize, image.is_external());
+    /// Load pixel data for all the images.
+    ///
+    /// Stops at the first image that fails to load, see [`SgFile::load_image_data_lenient`] for loading as many images as possible.
+    pub fn load_image_data<T, F: ImageBuilderFactory<T>>(&self, image_builder_factory: &F) -> Result<Vec<T>> {
+        self.load_image_data_with_options(image_builder_factory, &DecodeOptions::default())
+    }
 
-            if last_file_params != file_params {
-                let path = self.get_555_file_path(file_params.0, file_params.1);
-                reader = BufReader::new(File::open(path.clone())?);
-                last_file_params = file_params;
-            }
+    /// Load pixel data for all the images, decoded using the given options.
+    ///
+    /// Empty `tile_sizes` of the options are replaced by the ones of this file, the same goes for all the other loading functions.
+    pub fn load_image_data_with_options<T, F: ImageBuilderFactory<T>>(&self, image_builder_factory: &F, options: &DecodeOptions) -> Result<Vec<T>> {
+        self.load_image_data_with_resolver(&self.folder_resolver(), image_builder_factory, options)
+    }
 
-            result.push(image.load_image(&mut reader, image_factory_builder)?);
-        }
+    /// Load pixel data for all the images, with the 555 files opened using the provided resolver.
+    pub fn load_image_data_with_resolver<Res: FileResolver, T, F: ImageBuilderFactory<T>>(
+        &self,
+        resolver: &Res,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<Vec<T>> {
+        self.map_images(resolver, options, |image, reader, options| image.load_image_with_options(reader, image_builder_factory, options)).collect()
+    }
+
+    /// Load pixel data for the given images only, in the given order.
+    ///
+    /// Readers are reused for consecutive images stored in the same file.
+    pub fn load_selected_image_data<T, F: ImageBuilderFactory<T>>(
+        &self,
+        images: &[&SgImageMetadata],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<Vec<T>> {
+        let resolver = self.folder_resolver();
+        let options = self.file_decode_options(options);
+        let mut current_file = None;
+
+        images
+            .iter()
+            .map(|image| {
+                self.load_with_reader(&resolver, &mut current_file, image, &options, |image, reader, options| {
+                    image.load_image_with_options(reader, image_builder_factory, options)
+                })
+            })
+            .collect()
+    }
+
+    /// Load pixel data for a single image, with the 555 file containing it opened using the provided resolver.
+    pub fn load_image_with_resolver<Res: FileResolver, T, F: ImageBuilderFactory<T>>(
+        &self,
+        resolver: &Res,
+        image: &SgImageMetadata,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        let options = self.file_decode_options(options);
+
+        self.load_with_reader(resolver, &mut None, image, &options, |image, reader, options| {
+            image.load_image_with_options(reader, image_builder_factory, options)
+        })
+    }
+
+    /// Load pixel data for a single image from the contents of the 555 file containing it (or the image it mirrors).
+    ///
+    /// Decoded using the tile sizes of this file unless set in the options.
+    pub fn load_image_from_slice<T, F: ImageBuilderFactory<T>>(
+        &self,
+        image: &SgImageMetadata,
+        data_555: &[u8],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        let options = self.file_decode_options(options);
+
+        self.resolve_mirror(image)?.load_image_from_slice_with_options(data_555, image_builder_factory, &options)
+    }
+
+    /// Load pixel data for only one part of the given isometric image, decoded using the tile sizes of this file unless set in the options.
+    pub fn load_isometric_part<T, F: ImageBuilderFactory<T>>(
+        &self,
+        image: &SgImageMetadata,
+        part: IsometricPart,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        let options = self.file_decode_options(options);
+
+        self.load_with_reader(&self.folder_resolver(), &mut None, image, &options, |image, reader, options| {
+            image.load_isometric_part(reader, part, image_builder_factory, options)
+        })
+    }
 
-        Ok(result)
+    /// Load pixel data for all the images, with a separate result for each of them.
+    ///
+    /// An image failing to load does not prevent loading the remaining ones.
+    pub fn load_image_data_lenient<T, F: ImageBuilderFactory<T>>(&self, image_builder_factory: &F) -> Vec<Result<T>> {
+        self.load_image_data_lenient_with_options(image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for all the images decoded using the given options, with a separate result for each of them.
+    pub fn load_image_data_lenient_with_options<T, F: ImageBuilderFactory<T>>(&self, image_builder_factory: &F, options: &DecodeOptions) -> Vec<Result<T>> {
+        self.map_images(&self.folder_resolver(), options, |image, reader, options| image.load_image_with_options(reader, image_builder_factory, options))
+            .collect()
+    }
+
+    /// Load pixel data for all the images, decoding them in parallel.
+    ///
+    /// The order of the results matches the order of the images.
+    #[cfg(feature = "parallel")]
+    pub fn load_image_data_parallel<T: Send, F: ImageBuilderFactory<T> + Sync>(&self, image_builder_factory: &F) -> Result<Vec<T>> {
+        self.load_image_data_parallel_with_options(image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for all the images, decoding them in parallel using the given options.
+    #[cfg(feature = "parallel")]
+    pub fn load_image_data_parallel_with_options<T: Send, F: ImageBuilderFactory<T> + Sync>(
+        &self,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<Vec<T>> {
+        self.par_map_images(&self.folder_resolver(), options, |image, reader, options| image.load_image_with_options(reader, image_builder_factory, options))
+            .collect()
+    }
+
+    /// Load pixel data for all the images in parallel, with a separate result for each of them.
+    ///
+    /// The order of the results matches the order of the images.
+    #[cfg(feature = "parallel")]
+    pub fn load_image_data_parallel_lenient<T: Send, F: ImageBuilderFactory<T> + Sync>(&self, image_builder_factory: &F) -> Vec<Result<T>> {
+        self.load_image_data_parallel_lenient_with_options(image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for all the images in parallel using the given options, with a separate result for each of them.
+    #[cfg(feature = "parallel")]
+    pub fn load_image_data_parallel_lenient_with_options<T: Send, F: ImageBuilderFactory<T> + Sync>(
+        &self,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Vec<Result<T>> {
+        self.par_map_images(&self.folder_resolver(), options, |image, reader, options| image.load_image_with_options(reader, image_builder_factory, options))
+            .collect()
+    }
+
+    /// Apply `load` to each image along with a reader of the 555 file containing its data and the options completed for this file.
+    ///
+    /// Readers are reused for consecutive images stored in the same file.
+    fn map_images<'a, Res: FileResolver, X>(
+        &'a self,
+        resolver: &'a Res,
+        options: &DecodeOptions,
+        load: impl Fn(&ResolvedImage, &mut BufReader<Res::Reader>, &DecodeOptions) -> Result<X> + 'a,
+    ) -> impl Iterator<Item = Result<X>> + 'a {
+        let options = self.file_decode_options(options);
+        let mut current_file = None;
+
+        self.images.iter().map(move |image| self.load_with_reader(resolver, &mut current_file, image, &options, &load))
+    }
+
+    /// Parallel version of [`SgFile::map_images`], each job keeps its own readers.
+    #[cfg(feature = "parallel")]
+    fn par_map_images<'a, Res: FileResolver + Sync, X: Send>(
+        &'a self,
+        resolver: &'a Res,
+        options: &DecodeOptions,
+        load: impl Fn(&ResolvedImage, &mut BufReader<Res::Reader>, &DecodeOptions) -> Result<X> + Sync + Send + 'a,
+    ) -> impl IndexedParallelIterator<Item = Result<X>> + 'a {
+        let options = self.file_decode_options(options);
+
+        self.images
+            .par_iter()
+            .with_min_len(PARALLEL_MIN_IMAGES)
+            .map_init(|| None, move |current_file, image| self.load_with_reader(resolver, current_file, image, &options, &load))
+    }
+
+    fn load_with_reader<Res: FileResolver, X>(
+        &self,
+        resolver: &Res,
+        current_file: &mut Option<CachedReader<Res::Reader>>,
+        image: &SgImageMetadata,
+        options: &DecodeOptions,
+        load: impl Fn(&ResolvedImage, &mut BufReader<Res::Reader>, &DecodeOptions) -> Result<X>,
+    ) -> Result<X> {
+        let resolved = self.resolve_mirror(image)?;
+        let image = resolved.image;
+        let file_params = if image.is_external() { Some(image.album_id) } else { None };
+
+        let (_, path, reader) = match current_file.take() {
+            Some(file) if file.0 == file_params => current_file.insert(file),
+            _ => {
+                let filename = self.get_555_filename(image.album_id as usize, image.is_external())?;
+                // The path is looked up again only to report a failure
+                let (file, path) = resolver.open_with_path(&filename).map_err(|err| err.in_file(&resolver.path(&filename)))?;
+                current_file.insert((file_params, path, BufReader::new(file)))
+            }
+        };
+
+        load(&resolved, reader, options).map_err(|err| err.in_file(path))
     }
 
     /// Get path to the file containing pixel data for the given album.
-    pub fn get_555_file_path(&self, album_id: usize, is_external: bool) -> PathBuf {
+    ///
+    /// Caesar 3 keeps external pixel data either next to the sg2 file or in the `555` sub-folder, the former is preferred if it exists.
+    /// Filenames are matched case-insensitively, see [`FolderResolver`].
+    pub fn get_555_file_path(&self, album_id: usize, is_external: bool) -> Result<PathBuf> {
+        Ok(self.folder_resolver().path(&self.get_555_filename(album_id, is_external)?))
+    }
+
+    /// Get name of the file containing pixel data for the given album, as passed to [`FileResolver::open`].
+    pub fn get_555_filename(&self, album_id: usize, is_external: bool) -> Result<String> {
         let basename = if is_external {
-            &self.albums[album_id].external_filename
+            &self.albums.get(album_id).ok_or(SgImageError::UnknownAlbum(album_id))?.external_filename
         } else {
             &self.filename
         };
 
-        let filename = format!("{}.555", &basename[..basename.len() - 4]);
+        Ok(Self::to_555_filename(basename))
+    }
+
+    /// Options with the tile sizes of this file filled in, unless set already.
+    fn file_decode_options(&self, options: &DecodeOptions) -> DecodeOptions {
+        let mut options = options.clone();
+
+        if options.tile_sizes.is_empty() {
+            options.tile_sizes = self.profile().tile_sizes;
+        }
+
+        options
+    }
+
+    /// Resolver opening files from the folder of this sg file.
+    pub fn folder_resolver(&self) -> FolderResolver {
+        let resolver = FolderResolver::new(&self.folder);
+
+        if self.is_sg2() { resolver.with_sub_folder(SG2_EXTERNAL_FOLDER) } else { resolver }
+    }
+
+    fn to_555_filename(basename: &str) -> String {
+        Path::new(basename).with_extension("555").to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{ALPHA_VERSION, SG2_VERSION, SG3_VERSION};
+    use std::io::Cursor;
+
+    fn to_bytes(sg_file: &SgFile) -> Vec<u8> {
+        let mut data = Vec::new();
+        sg_file.write_to(&mut data).unwrap();
+        data
+    }
+
+    fn from_bytes(data: Vec<u8>, filename: &str) -> Result<SgFile> {
+        SgFile::load_from_reader(&mut BufReader::new(Cursor::new(data)), String::new(), String::from(filename))
+    }
+
+    /// File with a plain image followed by a mirror of it, whose record has its own offset and unknown bytes.
+    fn mirrored_file() -> SgFile {
+        let plain = SgImageMetadata { id: 1, length: 8, uncompressed_length: 8, width: 2, height: 2, ..Default::default() };
+        let mirrored = SgImageMetadata { id: 2, offset: 1234, invert_offset: -1, width: 2, height: 2, unknown_b: 7, ..Default::default() };
+
+        let mut sg_file = SgFile {
+            version: ALPHA_VERSION,
+            max_image_count: 4,
+            albums: vec![SgAlbum::default()],
+            images: vec![SgImageMetadata::default(), plain, mirrored],
+            ..Default::default()
+        };
+        sg_file.file_size = to_bytes(&sg_file).len() as u32;
+        sg_file
+    }
+
+    fn sg2_file(file_size: u32) -> SgFile {
+        SgFile {
+            file_size,
+            version: SG2_VERSION,
+            max_image_count: 10,
+            albums: vec![SgAlbum { external_filename: String::from("Ext.bmp"), ..Default::default() }],
+            images: vec![SgImageMetadata::default()],
+            ..Default::default()
+        }
+    }
+
+    /// Empty folder in the temporary directory, unique to the calling test.
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("sg_image_reader_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn sg2_files_have_100_album_records() {
+        let sg_file = sg2_file(SG2_FILE_SIZE);
+        let data = to_bytes(&sg_file);
+
+        // Header, 100 album records and 64 byte image records without alpha
+        assert_eq!(data.len(), 40 + HEADER_UNKNOWN_LENGTH + 100 * ALBUM_RECORD_LENGTH + 10 * 64);
+
+        let loaded = from_bytes(data, "c3.sg2").unwrap();
+
+        assert!(loaded.is_sg2());
+        assert!(!loaded.is_enemy_sg2());
+        assert_eq!(loaded.albums, sg_file.albums);
+        assert_eq!(loaded.images, sg_file.images);
+        assert_eq!(loaded.game, Game::Caesar3);
+    }
+
+    #[test]
+    fn enemy_sg2_files_are_detected_by_file_size() {
+        let loaded = from_bytes(to_bytes(&sg2_file(SG2_ENEMY_FILE_SIZE)), "Carthage.sg2").unwrap();
+
+        assert!(loaded.is_enemy_sg2());
+    }
+
+    #[test]
+    fn sg2_files_with_other_file_sizes_are_rejected() {
+        let err = from_bytes(to_bytes(&sg2_file(1234)), "c3.sg2").unwrap_err();
+
+        assert!(matches!(err, SgImageError::InvalidHeader), "{err:?}");
+    }
+
+    #[test]
+    fn sg3_files_must_match_their_file_size() {
+        let mut sg_file = mirrored_file();
+        sg_file.version = SG3_VERSION;
+        sg_file.file_size += 1;
+
+        let err = from_bytes(to_bytes(&sg_file), "Zeus_General.sg3").unwrap_err();
+
+        assert!(!sg_file.is_sg2());
+        assert!(matches!(err, SgImageError::FileSizeMismatch { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let mut data = to_bytes(&sg2_file(SG2_FILE_SIZE));
+        data[4..8].copy_from_slice(&0xd4_u32.to_le_bytes());
+
+        let err = from_bytes(data, "c3.sg2").unwrap_err();
+
+        assert!(matches!(err, SgImageError::UnsupportedVersion(0xd4)), "{err:?}");
+    }
+
+    #[test]
+    fn sg2_external_files_are_found_in_555_folder() {
+        let folder = temp_folder("sg2_555");
+        fs::create_dir_all(folder.join("555")).unwrap();
+        fs::write(folder.join("555").join("Ext.555"), []).unwrap();
+
+        let mut sg_file = sg2_file(SG2_FILE_SIZE);
+        sg_file.folder = folder.to_string_lossy().into_owned();
+
+        assert_eq!(sg_file.get_555_file_path(0, true).unwrap(), folder.join("555").join("Ext.555"));
+
+        // Files next to the sg2 file are preferred
+        fs::write(folder.join("Ext.555"), []).unwrap();
+
+        assert_eq!(sg_file.get_555_file_path(0, true).unwrap(), folder.join("Ext.555"));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn write_round_trips_mirrored_records() {
+        let sg_file = mirrored_file();
+        let data = to_bytes(&sg_file);
+
+        let loaded = from_bytes(data.clone(), "test.sg3").unwrap();
+
+        assert_eq!(loaded.images, sg_file.images);
+        assert_eq!(to_bytes(&loaded), data);
+    }
+
+    #[test]
+    fn images_past_the_image_records_are_rejected() {
+        let mut sg_file = mirrored_file();
+        sg_file.max_image_count = 2;
+
+        let err = sg_file.write_to(&mut Vec::new()).unwrap_err();
+
+        assert!(matches!(err, SgImageError::TooManyImages(3)), "{err:?}");
+    }
+
+    #[test]
+    fn mirrored_images_resolve_to_their_source() {
+        let sg_file = mirrored_file();
+
+        let resolved = sg_file.resolve_mirror(&sg_file.images[2]).unwrap();
+
+        assert_eq!(resolved.image, &sg_file.images[1]);
+        assert!(resolved.is_mirrored);
+        assert!(!sg_file.resolve_mirror(&sg_file.images[1]).unwrap().is_mirrored);
+    }
+
+    #[test]
+    fn unresolved_mirrors_are_not_decoded() {
+        let sg_file = mirrored_file();
+        let mirrored = &sg_file.images[2];
+
+        let err = mirrored.load_image(&mut BufReader::new(Cursor::new(Vec::new())), &VecImageBuilderFactory).unwrap_err();
+        assert!(matches!(err, SgImageError::UnresolvedMirror(2)), "{err:?}");
+
+        let err = mirrored.load_image_from_slice(&[0; 16], &VecImageBuilderFactory).unwrap_err();
+        assert!(matches!(err, SgImageError::UnresolvedMirror(2)), "{err:?}");
+
+        let err = mirrored.load_raw_data(&mut BufReader::new(Cursor::new(Vec::new()))).unwrap_err();
+        assert!(matches!(err, SgImageError::UnresolvedMirror(2)), "{err:?}");
+    }
+
+    /// Resolver serving files from memory.
+    struct MemoryResolver(BTreeMap<String, Vec<u8>>);
+
+    impl FileResolver for MemoryResolver {
+        type Reader = Cursor<Vec<u8>>;
+
+        fn open(&self, filename: &str) -> Result<Self::Reader> {
+            let data = self.0.get(filename).ok_or_else(|| SgImageError::FileNotFound { filename: String::from(filename), candidates: Vec::new() })?;
+            Ok(Cursor::new(data.clone()))
+        }
+    }
+
+    #[test]
+    fn options_reach_images_loaded_through_resolver() {
+        let mut sg_file = mirrored_file();
+        sg_file.filename = String::from("test.sg3");
+        let pixels: Vec<u8> = [[0xf8, 0, 0, 0xff], [0, 0xf8, 0, 0xff], [0, 0, 0xf8, 0xff], [0xf8, 0xf8, 0, 0xff]].concat();
+        let data_555 = sg_file.images[1].encode_image(&pixels, false, &[]).unwrap();
+
+        let resolver = MemoryResolver(BTreeMap::from([(String::from("test.sg3"), to_bytes(&sg_file)), (String::from("test.555"), data_555)]));
+
+        let (_, images) = SgFile::load_fully_from_resolver(&resolver, "test.sg3", &VecImageBuilderFactory, &DecodeOptions::default()).unwrap();
+        let flipped: Vec<u8> = pixels.chunks(8).flat_map(|row| [&row[4..], &row[..4]].concat()).collect();
+        assert_eq!(images[2], flipped);
+
+        let options = DecodeOptions::default().with_mirror(false);
+        let (_, images) = SgFile::load_fully_from_resolver(&resolver, "test.sg3", &VecImageBuilderFactory, &options).unwrap();
+        assert_eq!(images[2], pixels);
+    }
+
+    #[test]
+    fn truncated_image_records_report_their_position() {
+        let mut data = to_bytes(&mirrored_file());
+        let record_start = 40 + HEADER_UNKNOWN_LENGTH + 200 * ALBUM_RECORD_LENGTH + 72;
+        data.truncate(record_start + 10);
+
+        let err = from_bytes(data, "test.sg3").unwrap_err();
+
+        assert!(matches!(err, SgImageError::Image { id: 1, offset, .. } if offset == record_start as u64), "{err:?}");
+    }
+
+    #[test]
+    fn broken_mirror_chains_are_rejected() {
+        let mut sg_file = mirrored_file();
+        sg_file.images[1].invert_offset = 5;
+
+        let err = sg_file.resolve_mirror(&sg_file.images[2]).unwrap_err();
+
+        assert!(matches!(err, SgImageError::InvalidInvertOffset(1)), "{err:?}");
+    }
+
+    #[test]
+    fn paths_without_a_parent_are_rejected() {
+        let err = SgFile::load_from_path("/").unwrap_err();
+
+        assert!(matches!(err, SgImageError::InvalidPath(_)), "{err:?}");
+    }
+
+    #[test]
+    fn mirrors_of_following_images_are_rejected() {
+        let mut sg_file = mirrored_file();
+        sg_file.images[1].invert_offset = 1;
 
-        let path_buf: PathBuf = [&self.folder, &filename].iter().collect();
+        let err = from_bytes(to_bytes(&sg_file), "test.sg3").unwrap_err();
 
-        path_buf
+        assert!(matches!(err, SgImageError::InvalidInvertOffset(1)), "{err:?}");
     }
 }
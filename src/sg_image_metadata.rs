@@ -1,15 +1,128 @@
 use crate::Result;
 use crate::image_builder::{ImageBuilder, ImageBuilderFactory};
-use crate::{ReadHelper, SgImageError};
+use crate::pixel_format::PixelWriter;
+use crate::{ColourExpansion, DecodeOptions, ImageType, PixelFormat, ReadHelper, SgImageError, SliceReader, TileSize, WriteHelper};
 use std::io::BufReader;
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
+
+/// Part of an isometric image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IsometricPart {
+    /// The ground tiles covered by the image
+    Footprint,
+    /// The transparent part drawn above the footprint
+    Top,
+}
+
+/// Record holding the pixel data of an image, as returned by [`crate::SgFile::resolve_mirror`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedImage<'a> {
+    /// Record of the image the pixel data is stored for, the mirrored image for mirrors
+    pub image: &'a SgImageMetadata,
+    /// Whether the pixel data is flipped horizontally when decoded, set for mirrors
+    pub is_mirrored: bool,
+}
+
+impl ResolvedImage<'_> {
+    /// Load pixel data from the provided reader, flipped for mirrors.
+    pub fn load_image<T, F: ImageBuilderFactory<T>, R: Read + Seek>(&self, reader: &mut BufReader<R>, image_builder_factory: &F) -> Result<T> {
+        self.load_image_with_options(reader, image_builder_factory, &DecodeOptions::default())
+    }
 
-const ISOMETRIC_TILE_WIDTH: u16 = 58;
-const ISOMETRIC_TILE_HEIGHT: u16 = 30;
-const ISOMETRIC_TILE_BYTES: u16 = 1800;
-const ISOMETRIC_LARGE_TILE_WIDTH: u16 = 78;
-const ISOMETRIC_LARGE_TILE_HEIGHT: u16 = 40;
-const ISOMETRIC_LARGE_TILE_BYTES: u16 = 3200;
+    /// Load pixel data from the provided reader, flipped for mirrors unless disabled by [`DecodeOptions::mirror`].
+    pub fn load_image_with_options<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.image.load_image_flipped(reader, image_builder_factory, options, self.flip(options))
+    }
+
+    /// Load pixel data from the contents of the 555 file containing the resolved image, flipped for mirrors.
+    pub fn load_image_from_slice<T, F: ImageBuilderFactory<T>>(&self, data_555: &[u8], image_builder_factory: &F) -> Result<T> {
+        self.load_image_from_slice_with_options(data_555, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data from the contents of the 555 file containing the resolved image, decoded using the given options.
+    pub fn load_image_from_slice_with_options<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data_555: &[u8],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.image.decode_slice(data_555, image_builder_factory, options, self.flip(options))
+    }
+
+    /// Load pixel data from the raw data of the resolved image, flipped for mirrors unless disabled by [`DecodeOptions::mirror`].
+    pub fn load_image_from_raw_data_with_options<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data: &[u8],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.image.decode_raw_data(data, image_builder_factory, options, self.flip(options))
+    }
+
+    /// Load pixel data for only one part of an isometric image from the provided reader, flipped like the whole image.
+    pub fn load_isometric_part<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        part: IsometricPart,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        let data = self.image.load_raw_data(reader)?;
+
+        self.image.decode_part_from_raw_data(&data, part, image_builder_factory, options, self.flip(options))
+    }
+
+    fn flip(&self, options: &DecodeOptions) -> bool {
+        self.is_mirrored && options.mirror
+    }
+}
+
+/// Wraps a builder to only apply the alpha mask to pixels that were set, as the mask covers the whole image.
+struct PartImageBuilder<B> {
+    image_builder: B,
+    is_set: Vec<bool>,
+}
+
+impl<B> PartImageBuilder<B> {
+    fn new(image_builder: B, pixel_count: usize) -> Self {
+        PartImageBuilder { image_builder, is_set: vec![false; pixel_count] }
+    }
+}
+
+impl<T, B: ImageBuilder<T>> ImageBuilder<T> for PartImageBuilder<B> {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.is_set[position] = true;
+        self.image_builder.set_pixel_by_pos(position, data);
+    }
+
+    fn set_raw_pixel_by_pos(&mut self, position: usize, colour: u16) {
+        self.is_set[position] = true;
+        self.image_builder.set_raw_pixel_by_pos(position, colour);
+    }
+
+    fn set_keyed_pixel(&mut self, position: usize, colour: u16) {
+        self.image_builder.set_keyed_pixel(position, colour);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        if self.is_set[position] {
+            self.image_builder.set_alpha(position, alpha);
+        }
+    }
+
+    fn flip_horizontal(&mut self) {
+        self.image_builder.flip_horizontal();
+    }
+
+    fn build(self) -> T {
+        self.image_builder.build()
+    }
+}
 
 /// Metadata of an image.
 ///
@@ -17,6 +130,7 @@ const ISOMETRIC_LARGE_TILE_BYTES: u16 = 3200;
 ///
 /// Some bytes from the metadata are of unknown meaning.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SgImageMetadata {
     pub id: u32,
     pub offset: u32,
@@ -80,7 +194,7 @@ impl SgImageMetadata {
             0
         };
 
-        return Ok(SgImageMetadata {
+        Ok(SgImageMetadata {
             id,
             offset,
             length,
@@ -105,7 +219,45 @@ impl SgImageMetadata {
             unknown_f,
             alpha_offset,
             alpha_length,
-        });
+        })
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W, include_alpha: bool) -> Result<()> {
+        writer.write_u32_le(self.offset)?;
+        writer.write_u32_le(self.length)?;
+        writer.write_u32_le(self.uncompressed_length)?;
+        writer.write_all(&self.zeroes)?;
+        writer.write_i32_le(self.invert_offset)?;
+        writer.write_u16_le(self.width)?;
+        writer.write_u16_le(self.height)?;
+        for value in self.unknown_a {
+            writer.write_u16_le(value)?;
+        }
+        writer.write_u16_le(self.anim_sprites)?;
+        writer.write_u16_le(self.unknown_b)?;
+        writer.write_u16_le(self.x_offset)?;
+        writer.write_u16_le(self.y_offset)?;
+        writer.write_all(&self.unknown_c)?;
+        writer.write_u8(self.is_reversible)?;
+        writer.write_u8(self.unknown_d)?;
+        writer.write_u16_le(self.image_type)?;
+        writer.write_all(&self.flags)?;
+        writer.write_u8(self.album_id)?;
+        writer.write_u8(self.unknown_e)?;
+        writer.write_u8(self.anim_speed_id)?;
+        writer.write_all(&self.unknown_f)?;
+
+        if include_alpha {
+            writer.write_u32_le(self.alpha_offset)?;
+            writer.write_u32_le(self.alpha_length)?;
+        }
+
+        Ok(())
+    }
+
+    /// Category of the image, based on the raw `image_type`.
+    pub fn kind(&self) -> ImageType {
+        ImageType::from(self.image_type)
     }
 
     /// Checks if the image is flagged as having its data in an external file.
@@ -113,81 +265,378 @@ impl SgImageMetadata {
         self.flags[0] > 0
     }
 
-    /// Load pixel data for this image from the provided reader.
-    pub fn load_image<T, F: ImageBuilderFactory<T>, R: Read + Seek>(&self, reader: &mut BufReader<R>, image_builder_factory: &F) -> Result<T> {
-        let mut image_builder = image_builder_factory.new_builder(self.width, self.height);
+    /// Checks if part of the pixel data is compressed, as is the case for the transparent top part of isometric images.
+    ///
+    /// Images compressed fully are recognised by their type, see [`ImageType::is_compressed`].
+    pub fn has_compressed_part(&self) -> bool {
+        self.flags[1] != 0
+    }
 
-        if self.width == 0 || self.height == 0 || self.length == 0 {
-            return Ok(image_builder.build());
-        }
+    /// Size of an isometric image in tiles, 0 if it is not stored and has to be derived from the dimensions.
+    pub fn size_in_tiles(&self) -> u8 {
+        self.flags[3]
+    }
 
-        match self.image_type {
-            0 | 1 | 10 | 12 | 13 => self.load_plain_image(&mut image_builder, reader)?,
-            30 => self.load_isometric_image(&mut image_builder, reader)?,
-            256 | 257 | 276 => self.load_sprite_image(&mut image_builder, reader)?,
-            _ => return Err(SgImageError::UnknownImageType(self.image_type)),
-        }
+    /// Checks if the image is a mirrored copy of another image.
+    pub fn is_mirrored(&self) -> bool {
+        self.invert_offset != 0
+    }
+
+    /// Checks if the image has an alpha mask.
+    pub fn has_alpha(&self) -> bool {
+        self.alpha_length > 0
+    }
+
+    /// Offset of the sprite relative to its anchor point, stored as signed values.
+    pub fn sprite_offset(&self) -> (i16, i16) {
+        (self.x_offset as i16, self.y_offset as i16)
+    }
 
-        if self.alpha_length > 0 {
-            self.load_alpha_mask(&mut image_builder, reader)?;
+    /// Position of the pixel data of this image within its 555 file.
+    pub fn data_offset(&self) -> u64 {
+        match self.kind() {
+            ImageType::Plain => (self.offset as i64 - self.flags[0] as i64).max(0) as u64,
+            _ => self.offset as u64,
         }
+    }
 
-        if self.invert_offset != 0 {
-            image_builder.flip_horizontal();
+    /// Load the raw, still encoded, pixel data for this image from the provided reader.
+    ///
+    /// The returned bytes contain the image data immediately followed by the alpha mask (if there is one).
+    /// Mirrored images share their data with the image they mirror and fail with [`SgImageError::UnresolvedMirror`].
+    pub fn load_raw_data<R: Read + Seek>(&self, reader: &mut BufReader<R>) -> Result<Vec<u8>> {
+        self.check_resolved()?;
+
+        if self.length == 0 {
+            return Ok(Vec::new());
         }
 
-        Ok(image_builder.build())
+        let result = self.read_raw_data(reader);
+
+        result.map_err(|err| {
+            let offset = reader.stream_position().unwrap_or(self.data_offset());
+            err.in_image(self.id, self.album_id, offset)
+        })
     }
 
-    fn load_plain_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
+    fn read_raw_data<R: Read + Seek>(&self, reader: &mut BufReader<R>) -> Result<Vec<u8>> {
         let current_position = reader.stream_position()?;
 
-        let relative_position = self.offset as i64 - self.flags[0] as i64 - current_position as i64;
+        // Relative seeking keeps the buffer if the data is close by
+        let relative_position = self.data_offset() as i64 - current_position as i64;
 
         if relative_position != 0 {
             reader.seek_relative(relative_position)?;
         }
 
+        reader.read_vec(self.raw_data_length())
+    }
+
+    fn raw_data_length(&self) -> usize {
+        self.length as usize + self.alpha_length as usize
+    }
+
+    fn has_pixel_data(&self) -> bool {
+        self.width != 0 && self.height != 0 && self.length != 0
+    }
+
+    /// Records of mirrored images have no pixel data of their own, decoding them would silently give an empty image.
+    fn check_resolved(&self) -> Result<()> {
+        if self.is_mirrored() {
+            return Err(SgImageError::UnresolvedMirror(self.id));
+        }
+        Ok(())
+    }
+
+    /// Load pixel data for this image from the provided reader.
+    ///
+    /// Errors are reported as [`SgImageError::Image`], containing the id of this image and the position in the stream at which the failure happened.
+    /// Records of mirrored images have to be resolved using [`crate::SgFile::resolve_mirror`] first, they fail with [`SgImageError::UnresolvedMirror`].
+    pub fn load_image<T, F: ImageBuilderFactory<T>, R: Read + Seek>(&self, reader: &mut BufReader<R>, image_builder_factory: &F) -> Result<T> {
+        self.load_image_with_options(reader, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for this image from the provided reader, decoded using the given options.
+    pub fn load_image_with_options<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.check_resolved()?;
+        self.load_image_flipped(reader, image_builder_factory, options, false)
+    }
+
+    fn load_image_flipped<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+        flip: bool,
+    ) -> Result<T> {
+        if !self.has_pixel_data() {
+            return Ok(image_builder_factory.new_builder(self.width, self.height).build());
+        }
+
+        let data = self.load_raw_data(reader)?;
+
+        self.decode_raw_data(&data, image_builder_factory, options, flip)
+    }
+
+    /// Load pixel data for this image from the contents of the 555 file containing it.
+    ///
+    /// Can be used with memory mapped files or data that is already in memory.
+    pub fn load_image_from_slice<T, F: ImageBuilderFactory<T>>(&self, data_555: &[u8], image_builder_factory: &F) -> Result<T> {
+        self.load_image_from_slice_with_options(data_555, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for this image from the contents of the 555 file containing it, decoded using the given options.
+    pub fn load_image_from_slice_with_options<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data_555: &[u8],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.check_resolved()?;
+        self.decode_slice(data_555, image_builder_factory, options, false)
+    }
+
+    fn decode_slice<T, F: ImageBuilderFactory<T>>(&self, data_555: &[u8], image_builder_factory: &F, options: &DecodeOptions, flip: bool) -> Result<T> {
+        if !self.has_pixel_data() {
+            return Ok(image_builder_factory.new_builder(self.width, self.height).build());
+        }
+
+        let start = self.data_offset() as usize;
+        let end = start + self.raw_data_length();
+
+        let Some(data) = data_555.get(start..end) else {
+            let err = SgImageError::ImageDataLengthMismatch { expected: end as u64, actual: data_555.len() as u64 };
+            return Err(err.in_image(self.id, self.album_id, start as u64));
+        };
+
+        self.decode_raw_data(data, image_builder_factory, options, flip)
+    }
+
+    /// Load pixel data for this image from its raw data, as returned by [`SgImageMetadata::load_raw_data`].
+    pub fn load_image_from_raw_data<T, F: ImageBuilderFactory<T>>(&self, data: &[u8], image_builder_factory: &F) -> Result<T> {
+        self.load_image_from_raw_data_with_options(data, image_builder_factory, &DecodeOptions::default())
+    }
+
+    /// Load pixel data for this image from its raw data, decoded using the given options.
+    pub fn load_image_from_raw_data_with_options<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data: &[u8],
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.check_resolved()?;
+        self.decode_raw_data(data, image_builder_factory, options, false)
+    }
+
+    fn decode_raw_data<T, F: ImageBuilderFactory<T>>(&self, data: &[u8], image_builder_factory: &F, options: &DecodeOptions, flip: bool) -> Result<T> {
+        self.decode_from_raw_data(data, |reader| {
+            let mut image_builder = image_builder_factory.new_builder(self.width, self.height);
+            let (format, expansion) = (image_builder_factory.pixel_format(), image_builder_factory.colour_expansion());
+            self.decode_image(&mut image_builder, reader, None, format, expansion, options)?;
+
+            if flip {
+                image_builder.flip_horizontal();
+            }
+
+            Ok(image_builder.build())
+        })
+    }
+
+    /// Size of an isometric image in tiles (the length of a side of its footprint), `None` for other images.
+    ///
+    /// See [`SgImageMetadata::isometric_geometry`] for how the tile sizes are used.
+    pub fn footprint_size(&self, tile_sizes: &[TileSize]) -> Option<u16> {
+        self.isometric_geometry(tile_sizes).ok().map(|geometry| geometry.size)
+    }
+
+    /// Load pixel data for only one part of this isometric image from the provided reader.
+    ///
+    /// Fails with [`SgImageError::NotIsometric`] for other types of images.
+    pub fn load_isometric_part<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        part: IsometricPart,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        let data = self.load_raw_data(reader)?;
+
+        self.load_isometric_part_from_raw_data(&data, part, image_builder_factory, options)
+    }
+
+    /// Load pixel data for the footprint and the top of this isometric image as separate images, from the provided reader.
+    pub fn load_isometric_parts<T, F: ImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<(T, T)> {
+        let data = self.load_raw_data(reader)?;
+
+        let footprint = self.load_isometric_part_from_raw_data(&data, IsometricPart::Footprint, image_builder_factory, options)?;
+        let top = self.load_isometric_part_from_raw_data(&data, IsometricPart::Top, image_builder_factory, options)?;
+
+        Ok((footprint, top))
+    }
+
+    /// Load pixel data for only one part of this isometric image from its raw data, as returned by [`SgImageMetadata::load_raw_data`].
+    pub fn load_isometric_part_from_raw_data<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data: &[u8],
+        part: IsometricPart,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Result<T> {
+        self.check_resolved()?;
+        self.decode_part_from_raw_data(data, part, image_builder_factory, options, false)
+    }
+
+    fn decode_part_from_raw_data<T, F: ImageBuilderFactory<T>>(
+        &self,
+        data: &[u8],
+        part: IsometricPart,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+        flip: bool,
+    ) -> Result<T> {
+        self.decode_from_raw_data(data, |reader| {
+            if self.kind() != ImageType::Isometric {
+                return Err(SgImageError::NotIsometric(self.image_type));
+            }
+
+            let mut image_builder = PartImageBuilder::new(image_builder_factory.new_builder(self.width, self.height), self.pixel_count());
+            let (format, expansion) = (image_builder_factory.pixel_format(), image_builder_factory.colour_expansion());
+            self.decode_image(&mut image_builder, reader, Some(part), format, expansion, options)?;
+
+            if flip {
+                image_builder.flip_horizontal();
+            }
+
+            Ok(image_builder.build())
+        })
+    }
+
+    fn decode_from_raw_data<T>(&self, data: &[u8], decode: impl FnOnce(&mut SliceReader) -> Result<T>) -> Result<T> {
+        let mut reader = SliceReader::new(data);
+
+        let result = decode(&mut reader);
+
+        result.map_err(|err| err.in_image(self.id, self.album_id, self.data_offset() + reader.position() as u64))
+    }
+
+    fn decode_image<T, B: ImageBuilder<T>>(
+        &self,
+        image_builder: &mut B,
+        reader: &mut SliceReader,
+        part: Option<IsometricPart>,
+        format: PixelFormat,
+        expansion: ColourExpansion,
+        options: &DecodeOptions,
+    ) -> Result<()> {
+        if !self.has_pixel_data() {
+            return Ok(());
+        }
+
+        let has_alpha = self.has_alpha() && options.apply_alpha;
+
+        // Premultiplied colours need the alpha mask, which is stored after them
+        let premultiply = has_alpha && format.is_premultiplied();
+
+        let alpha_mask = if premultiply {
+            let mut alpha_reader = reader.clone();
+            alpha_reader.skip(self.length as usize)?;
+            let mut alpha_mask = vec![0xff; self.pixel_count()];
+            self.load_alpha_mask(&mut alpha_reader, expansion, |position, alpha| {
+                alpha_mask[position] = alpha;
+                Ok(())
+            })?;
+            Some(alpha_mask)
+        } else {
+            None
+        };
+
+        let key_colour = options.key_colour.filter(|_| options.key_plain_images || self.kind() != ImageType::Plain);
+        let mut writer = PixelWriter::new(image_builder, format, expansion, key_colour, self.pixel_count(), alpha_mask);
+
+        match self.kind() {
+            ImageType::Plain => self.load_plain_image(&mut writer, reader)?,
+            ImageType::Isometric => self.load_isometric_image(&mut writer, reader, part, &options.tile_sizes)?,
+            ImageType::Sprite | ImageType::Font => self.load_sprite_image(&mut writer, reader)?,
+            ImageType::Unknown(image_type) => return Err(SgImageError::UnknownImageType(image_type)),
+        }
+
+        if premultiply {
+            reader.skip(self.alpha_length as usize)?;
+        } else if has_alpha {
+            self.load_alpha_mask(reader, expansion, |position, alpha| writer.set_alpha(position, alpha))?;
+        }
+
+        Ok(())
+    }
+
+    fn load_plain_image<T, B: ImageBuilder<T>>(&self, writer: &mut PixelWriter<B>, reader: &mut SliceReader) -> Result<()> {
         // Check image data
-        if self.height as u32 * self.width as u32 * 2 != self.length {
-            return Err(SgImageError::ImageDataLengthMismatch);
+        if self.pixel_count() * 2 != self.length as usize {
+            return Err(SgImageError::ImageDataLengthMismatch { expected: self.pixel_count() as u64 * 2, actual: self.length as u64 });
         }
 
         for position in 0..(self.length as usize) / 2 {
             let colour = reader.read_u16_le()?;
-            Self::set_555_pixel_by_pos(image_builder, position, colour);
+            writer.set_555_pixel(position, colour)?;
         }
 
         Ok(())
     }
 
-    fn load_isometric_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
-        let current_position = reader.stream_position()?;
-
-        let relative_position = self.offset as i64 - current_position as i64;
+    fn load_isometric_image<T, B: ImageBuilder<T>>(
+        &self,
+        writer: &mut PixelWriter<B>,
+        reader: &mut SliceReader,
+        part: Option<IsometricPart>,
+        tile_sizes: &[TileSize],
+    ) -> Result<()> {
+        let transparent_length = self.length.checked_sub(self.uncompressed_length).ok_or(SgImageError::ImageDataLengthMismatch {
+            expected: self.uncompressed_length as u64,
+            actual: self.length as u64,
+        })?;
 
-        if relative_position != 0 {
-            reader.seek_relative(relative_position)?;
+        if part == Some(IsometricPart::Top) {
+            reader.skip(self.uncompressed_length as usize)?;
+        } else {
+            self.load_isometric_base(writer, reader, tile_sizes)?;
         }
 
-        self.load_isometric_base(image_builder, reader)?;
-        self.load_transparent_image(image_builder, reader, &(self.length - self.uncompressed_length))?;
+        if part == Some(IsometricPart::Footprint) {
+            reader.skip(transparent_length as usize)?;
+        } else {
+            self.load_transparent_image(writer, reader, &transparent_length)?;
+        }
 
         Ok(())
     }
 
-    fn load_isometric_base<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut R) -> Result<()> {
-        let width = self.width;
-        let height = (width + 2) / 2; // 58 -> 39, 118 -> 60 etc
-        let size = self.calculate_isometric_size(height);
-        let (_tile_bytes, tile_height, tile_width) = Self::calculate_tile_size(&size, &height);
-        let height_offset = self.height - height;
+    fn load_isometric_base<T, B: ImageBuilder<T>>(&self, writer: &mut PixelWriter<B>, reader: &mut SliceReader, tile_sizes: &[TileSize]) -> Result<()> {
+        for position in self.isometric_base_positions(tile_sizes)? {
+            let c = reader.read_u16_le()?;
+            writer.set_555_pixel(position, c)?;
+        }
 
-        let mut y_offset = height_offset;
+        Ok(())
+    }
 
-        if ((width as u32 + 2) * height as u32) != self.uncompressed_length {
-            return Err(SgImageError::ImageDataLengthMismatch);
-        }
+    /// Positions of the pixels making up the isometric base, in the order they are stored in.
+    pub(crate) fn isometric_base_positions(&self, tile_sizes: &[TileSize]) -> Result<Vec<usize>> {
+        let geometry = self.isometric_geometry(tile_sizes)?;
+        let height_offset = self.height as u32 - geometry.height();
+
+        let (size, tile_width, tile_height) = (geometry.size as usize, geometry.tile_size.width as usize, geometry.tile_size.height as usize);
+        let mut y_offset = height_offset as usize;
+        let mut positions = Vec::with_capacity(self.uncompressed_length as usize / 2);
 
         for y in 0..(size + size - 1) {
             let (x_lim, mut x_offset) = if y < size {
@@ -197,49 +646,22 @@ impl SgImageMetadata {
             };
 
             for _x in 0..x_lim {
-                self.write_isometric_tile(image_builder, reader, x_offset as usize, y_offset as usize, tile_width as usize, tile_height as usize)?;
+                self.push_isometric_tile_positions(&mut positions, x_offset, y_offset, tile_width, tile_height);
                 x_offset += tile_width + 2;
             }
 
             y_offset += tile_height / 2;
         }
 
-        Ok(())
+        Ok(positions)
     }
 
-    fn load_sprite_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
-        let current_position = reader.stream_position()?;
-
-        let relative_position = self.offset as i64 - current_position as i64;
-
-        if relative_position != 0 {
-            reader.seek_relative(relative_position)?;
-        }
-
-        self.load_transparent_image(image_builder, reader, &self.length)?;
+    fn load_sprite_image<T, B: ImageBuilder<T>>(&self, writer: &mut PixelWriter<B>, reader: &mut SliceReader) -> Result<()> {
+        self.load_transparent_image(writer, reader, &self.length)?;
         Ok(())
     }
 
-    fn calculate_isometric_size(&self, height: u16) -> u16 {
-        if self.flags[3] == 0 {
-            if (height % ISOMETRIC_TILE_HEIGHT) == 0 {
-                return height / ISOMETRIC_TILE_HEIGHT;
-            } else if (height % ISOMETRIC_LARGE_TILE_HEIGHT) == 0 {
-                return height / ISOMETRIC_LARGE_TILE_HEIGHT;
-            }
-        }
-        self.flags[3] as u16
-    }
-
-    fn write_isometric_tile<T, B: ImageBuilder<T>, R: Read + Seek>(
-        &self,
-        image_builder: &mut B,
-        reader: &mut R,
-        offset_x: usize,
-        offset_y: usize,
-        tile_width: usize,
-        tile_height: usize,
-    ) -> Result<()> {
+    fn push_isometric_tile_positions(&self, positions: &mut Vec<usize>, offset_x: usize, offset_y: usize, tile_width: usize, tile_height: usize) {
         let half_height = tile_height / 2;
 
         let mut x_start = tile_height;
@@ -252,8 +674,7 @@ impl SgImageMetadata {
             x_end += 2;
             position += x_start;
             for _x in x_start..x_end {
-                let c = reader.read_u16_le()?;
-                Self::set_555_pixel_by_pos(image_builder, position, c);
+                positions.push(position);
                 position += 1;
             }
             position += x_start + skip;
@@ -262,27 +683,16 @@ impl SgImageMetadata {
         for _y in half_height..tile_height {
             position += x_start;
             for _x in x_start..x_end {
-                let c = reader.read_u16_le()?;
-                Self::set_555_pixel_by_pos(image_builder, position, c);
+                positions.push(position);
                 position += 1;
             }
             position += x_start + skip;
             x_start += 2;
             x_end -= 2;
         }
-
-        Ok(())
-    }
-
-    fn calculate_tile_size(size: &u16, height: &u16) -> (u16, u16, u16) {
-        if ISOMETRIC_TILE_HEIGHT * size == *height {
-            (ISOMETRIC_TILE_BYTES, ISOMETRIC_TILE_HEIGHT, ISOMETRIC_TILE_WIDTH)
-        } else {
-            (ISOMETRIC_LARGE_TILE_BYTES, ISOMETRIC_LARGE_TILE_HEIGHT, ISOMETRIC_LARGE_TILE_WIDTH)
-        }
     }
 
-    fn load_transparent_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>, length: &u32) -> Result<()> {
+    fn load_transparent_image<T, B: ImageBuilder<T>>(&self, writer: &mut PixelWriter<B>, reader: &mut SliceReader, length: &u32) -> Result<()> {
         let mut pos = 0;
         let mut remaining_bytes = *length as usize;
 
@@ -292,13 +702,13 @@ impl SgImageMetadata {
             if c == 255 {
                 // The next number is pixels to skip
                 pos += reader.read_u8()? as usize;
-                remaining_bytes -= 2;
+                remaining_bytes = Self::consume_run(remaining_bytes, 2, *length)?;
             } else {
-                remaining_bytes -= 1 + (c * 2);
+                remaining_bytes = Self::consume_run(remaining_bytes, 1 + (c * 2), *length)?;
                 // Pixels to fill in
                 for _j in 0..c {
                     let pixel = reader.read_u16_le()?;
-                    Self::set_555_pixel_by_pos(image_builder, pos, pixel);
+                    writer.set_555_pixel(pos, pixel)?;
                     pos += 1;
                 }
             }
@@ -307,7 +717,12 @@ impl SgImageMetadata {
         Ok(())
     }
 
-    fn load_alpha_mask<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut R) -> Result<()> {
+    fn load_alpha_mask(
+        &self,
+        reader: &mut SliceReader,
+        expansion: ColourExpansion,
+        mut set_alpha: impl FnMut(usize, u8) -> Result<()>,
+    ) -> Result<()> {
         let mut pos = 0;
         let mut remaining_bytes = self.alpha_length as usize;
 
@@ -317,13 +732,14 @@ impl SgImageMetadata {
             if c == 255 {
                 // The next number is pixels to skip
                 pos += reader.read_u8()? as usize;
-                remaining_bytes -= 2;
+                remaining_bytes = Self::consume_run(remaining_bytes, 2, self.alpha_length)?;
             } else {
                 // Pixels to fill in
-                remaining_bytes -= 1 + c;
+                remaining_bytes = Self::consume_run(remaining_bytes, 1 + c, self.alpha_length)?;
                 for _j in 0..c {
                     let alpha = reader.read_u8()?;
-                    image_builder.set_alpha(pos, alpha << 3);
+                    self.check_position(pos)?;
+                    set_alpha(pos, expansion.expand(alpha))?;
                     pos += 1;
                 }
             }
@@ -332,19 +748,144 @@ impl SgImageMetadata {
         Ok(())
     }
 
-    fn set_555_pixel_by_pos<T, B: ImageBuilder<T>>(builder: &mut B, position: usize, colour: u16) {
-        if colour == 0xf81f {
-            return;
+    fn consume_run(remaining_bytes: usize, run_length: usize, length: u32) -> Result<usize> {
+        remaining_bytes.checked_sub(run_length).ok_or(SgImageError::ImageDataLengthMismatch {
+            expected: length as u64,
+            actual: (length as usize - remaining_bytes + run_length) as u64,
+        })
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    fn check_position(&self, position: usize) -> Result<()> {
+        if position >= self.pixel_count() {
+            return Err(SgImageError::PixelOutOfBounds(position));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VecImageBuilderFactory;
+    use std::io::ErrorKind;
+
+    /// Decode crafted raw data, returning the error without the image context.
+    fn decode_error(image: &SgImageMetadata, data: &[u8]) -> SgImageError {
+        match image.load_image_from_raw_data(data, &VecImageBuilderFactory).unwrap_err() {
+            SgImageError::Image { id, source, .. } if id == image.id => *source,
+            err => panic!("missing image context: {err:?}"),
         }
+    }
+
+    fn sprite(width: u16, height: u16, data: &[u8]) -> SgImageMetadata {
+        SgImageMetadata { id: 7, width, height, image_type: 256, length: data.len() as u32, ..Default::default() }
+    }
+
+    /// Isometric image of a single tile with a column drawn above it, part of the column is translucent.
+    fn isometric_image() -> (SgImageMetadata, Vec<u8>, Vec<usize>) {
+        let mut image = SgImageMetadata { id: 3, width: 58, height: 40, image_type: 30, uncompressed_length: 1800, ..Default::default() };
+        let base = image.isometric_base_positions(&[]).unwrap();
+
+        let pixels: Vec<u8> = (0..58 * 40)
+            .flat_map(|position| match (base.contains(&position), position % 58, position / 58) {
+                (true, _, _) => [0x08, 0x10, 0x18, 0xff],
+                (false, 29, row) if row < 4 => [0xf8, 0, 0, 0x80],
+                (false, 29, _) => [0, 0xf8, 0, 0xff],
+                _ => [0; 4],
+            })
+            .collect();
+
+        let data = image.encode_image(&pixels, true, &[]).unwrap();
+        (image, data, base)
+    }
+
+    /// Pixels of the given image, with the ones not matching the filter left transparent.
+    fn filter_pixels(pixels: &[u8], keep: impl Fn(usize) -> bool) -> Vec<u8> {
+        pixels.chunks(4).enumerate().flat_map(|(position, pixel)| if keep(position) { pixel } else { &[0; 4] }).copied().collect()
+    }
+
+    #[test]
+    fn isometric_parts_are_decoded_separately() {
+        let (image, data, base) = isometric_image();
+        let pixels = image.load_image_from_raw_data(&data, &VecImageBuilderFactory).unwrap();
+        assert!(image.has_alpha());
+
+        let options = DecodeOptions::default();
+        let footprint = image.load_isometric_part_from_raw_data(&data, IsometricPart::Footprint, &VecImageBuilderFactory, &options).unwrap();
+        let top = image.load_isometric_part_from_raw_data(&data, IsometricPart::Top, &VecImageBuilderFactory, &options).unwrap();
+
+        // The alpha mask covers the whole image, but only applies to pixels of the decoded part
+        assert_eq!(footprint, filter_pixels(&pixels, |position| base.contains(&position)));
+        assert_eq!(top, filter_pixels(&pixels, |position| !base.contains(&position)));
+        assert_eq!(&top[29 * 4..30 * 4], [0xf8, 0, 0, 0x80]);
+
+        let mut reader = BufReader::new(std::io::Cursor::new(data));
+        assert_eq!(image.load_isometric_parts(&mut reader, &VecImageBuilderFactory, &options).unwrap(), (footprint, top));
+    }
+
+    #[test]
+    fn only_isometric_images_have_parts() {
+        let data = [1, 0, 0];
+        let err = sprite(1, 1, &data).load_isometric_part_from_raw_data(&data, IsometricPart::Top, &VecImageBuilderFactory, &DecodeOptions::default());
+
+        assert!(matches!(err, Err(SgImageError::Image { source, .. }) if matches!(*source, SgImageError::NotIsometric(256))));
+    }
+
+    #[test]
+    fn runs_longer_than_the_data_are_rejected() {
+        // A run of 2 pixels needs 5 bytes
+        let data = [2, 0, 0, 0, 0];
+        let mut image = sprite(4, 1, &data);
+        image.length = 3;
 
-        let ones = 0xf8_u8;
-        let r = (colour >> 7) as u8 & ones;
-        let g = (colour >> 2) as u8 & ones;
-        let b = (colour << 3) as u8 & ones;
+        assert!(matches!(decode_error(&image, &data), SgImageError::ImageDataLengthMismatch { expected: 3, actual: 5 }));
+    }
+
+    #[test]
+    fn skips_past_the_image_are_rejected() {
+        let data = [255, 5, 1, 0, 0];
+
+        assert!(matches!(decode_error(&sprite(2, 1, &data), &data), SgImageError::PixelOutOfBounds(5)));
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let data = [2, 0, 0];
+
+        assert!(matches!(decode_error(&sprite(2, 1, &[0; 5]), &data), SgImageError::IoError(err) if err.kind() == ErrorKind::UnexpectedEof));
+    }
 
-        let data = [r, g, b, 0xff];
+    #[test]
+    fn truncated_alpha_masks_are_rejected() {
+        let data = [1, 0, 0x7c, 2];
+        let mut image = sprite(2, 1, &data[..3]);
+        image.alpha_length = 3;
 
-        builder.set_pixel_by_pos(position, data);
+        assert!(matches!(decode_error(&image, &data), SgImageError::IoError(err) if err.kind() == ErrorKind::UnexpectedEof));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn alpha_past_the_image_is_rejected() {
+        let data = [1, 0, 0x7c, 255, 2, 1, 0xff];
+        let mut image = sprite(2, 1, &data[..3]);
+        image.alpha_length = 4;
+
+        assert!(matches!(decode_error(&image, &data), SgImageError::PixelOutOfBounds(2)));
+    }
+
+    #[test]
+    fn inconsistent_lengths_are_rejected() {
+        let plain = SgImageMetadata { width: 2, height: 2, length: 6, ..Default::default() };
+        assert!(matches!(decode_error(&plain, &[0; 6]), SgImageError::ImageDataLengthMismatch { expected: 8, actual: 6 }));
+
+        let isometric = SgImageMetadata { width: 58, height: 30, image_type: 30, length: 10, uncompressed_length: 1800, ..Default::default() };
+        assert!(matches!(decode_error(&isometric, &[0; 10]), SgImageError::ImageDataLengthMismatch { expected: 1800, actual: 10 }));
+
+        let unknown = SgImageMetadata { width: 1, height: 1, image_type: 99, length: 2, ..Default::default() };
+        assert!(matches!(decode_error(&unknown, &[0; 2]), SgImageError::UnknownImageType(99)));
+    }
+}
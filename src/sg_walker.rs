@@ -0,0 +1,169 @@
+use crate::{DecodeOptions, ImageBuilderFactory, Result, SgFile};
+
+/// Number of directions walker sprites are drawn in.
+pub const WALKER_DIRECTIONS: usize = 8;
+
+/// Direction a walker is facing, as seen on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    TopLeft,
+}
+
+impl Direction {
+    /// All the directions, in the order their frames are stored in.
+    pub const ALL: [Direction; WALKER_DIRECTIONS] = [
+        Direction::Top,
+        Direction::TopRight,
+        Direction::Right,
+        Direction::BottomRight,
+        Direction::Bottom,
+        Direction::BottomLeft,
+        Direction::Left,
+        Direction::TopLeft,
+    ];
+
+    /// Position of the frame for this direction within a group of frames.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Frames of a walker sprite.
+///
+/// Walker frames are stored interleaved, all the directions of the first frame followed by all the directions of the next one etc.
+/// Some of the directions are mirrored from the opposite ones (see `invert_offset` of [`crate::SgImageMetadata`]).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SgWalker {
+    /// Index of the first image of the walker
+    pub base_image: usize,
+    /// Number of frames for each direction
+    pub frame_count: usize,
+}
+
+/// Image used for a single frame of a walker facing a given direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SgWalkerFrame {
+    /// Index of the image of this frame
+    pub image: usize,
+    /// Index of the image containing the pixel data, differs from `image` for mirrored frames
+    pub source_image: usize,
+    /// Whether the pixel data of the source image is mirrored horizontally
+    pub is_mirrored: bool,
+}
+
+impl SgWalker {
+    /// Index of the image for the given direction and frame.
+    pub fn image(&self, direction: Direction, frame: usize) -> Option<usize> {
+        if frame >= self.frame_count {
+            return None;
+        }
+
+        WALKER_DIRECTIONS.checked_mul(frame)?.checked_add(direction.index())?.checked_add(self.base_image)
+    }
+
+    /// Indices of the images for all the frames of the given direction.
+    pub fn images(&self, direction: Direction) -> impl Iterator<Item = usize> + '_ {
+        (0..self.frame_count).filter_map(move |frame| self.image(direction, frame))
+    }
+}
+
+impl SgFile {
+    /// Walker sprite starting at the image with the given index, with the given number of frames for each direction.
+    ///
+    /// Returns `None` if the file does not contain all the frames.
+    pub fn walker(&self, base_image: usize, frame_count: usize) -> Option<SgWalker> {
+        let end = frame_count.checked_mul(WALKER_DIRECTIONS)?.checked_add(base_image)?;
+
+        if end > self.images.len() {
+            return None;
+        }
+
+        Some(SgWalker { base_image, frame_count })
+    }
+
+    /// Frame of a walker facing the given direction, with mirroring resolved like [`SgFile::resolve_mirror`] does.
+    pub fn walker_frame(&self, walker: &SgWalker, direction: Direction, frame: usize) -> Option<SgWalkerFrame> {
+        let index = walker.image(direction, frame)?;
+        let resolved = self.resolve_mirror(self.images.get(index)?).ok()?;
+
+        Some(SgWalkerFrame { image: index, source_image: resolved.image.id as usize, is_mirrored: resolved.is_mirrored })
+    }
+
+    /// Load pixel data of a walker frame decoded using the given options, mirrored frames are flipped unless disabled in them.
+    ///
+    /// Returns `None` if the walker does not have the given frame.
+    pub fn load_walker_frame<T, F: ImageBuilderFactory<T>>(
+        &self,
+        walker: &SgWalker,
+        direction: Direction,
+        frame: usize,
+        image_builder_factory: &F,
+        options: &DecodeOptions,
+    ) -> Option<Result<T>> {
+        let image = self.images.get(walker.image(direction, frame)?)?;
+
+        Some(self.load_image_with_resolver(&self.folder_resolver(), image, image_builder_factory, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, SgFile, SgImageMetadata, SgWalker, SgWalkerFrame};
+
+    #[test]
+    fn oversized_walkers_are_rejected() {
+        let sg_file = SgFile { images: vec![SgImageMetadata::default(); 17], ..Default::default() };
+
+        assert!(sg_file.walker(1, 2).is_some());
+        assert!(sg_file.walker(2, 2).is_none());
+        assert!(sg_file.walker(1, usize::MAX / 4).is_none());
+        assert!(sg_file.walker(usize::MAX, 1).is_none());
+    }
+
+    #[test]
+    fn frames_are_interleaved_by_direction() {
+        let walker = SgWalker { base_image: 1, frame_count: 2 };
+
+        assert_eq!(walker.image(Direction::Top, 0), Some(1));
+        assert_eq!(walker.image(Direction::Right, 1), Some(11));
+        assert_eq!(walker.image(Direction::TopLeft, 1), Some(16));
+        assert_eq!(walker.image(Direction::Top, 2), None);
+        assert_eq!(walker.images(Direction::Bottom).collect::<Vec<_>>(), [5, 13]);
+    }
+
+    #[test]
+    fn mirrored_frames_resolve_to_their_source() {
+        let mut images: Vec<_> = (0..17).map(|id| SgImageMetadata { id, ..Default::default() }).collect();
+        // Left mirrors Right, TopLeft mirrors Left and so Right as well
+        images[7].invert_offset = -4;
+        images[8].invert_offset = -1;
+
+        let sg_file = SgFile { images, ..Default::default() };
+        let walker = sg_file.walker(1, 2).unwrap();
+
+        let frame = |direction| sg_file.walker_frame(&walker, direction, 0).unwrap();
+
+        assert_eq!(frame(Direction::Right), SgWalkerFrame { image: 3, source_image: 3, is_mirrored: false });
+        assert_eq!(frame(Direction::Left), SgWalkerFrame { image: 7, source_image: 3, is_mirrored: true });
+        assert_eq!(frame(Direction::TopLeft), SgWalkerFrame { image: 8, source_image: 3, is_mirrored: true });
+    }
+
+    #[test]
+    fn frame_images_do_not_overflow() {
+        let walker = SgWalker { base_image: usize::MAX - 8, frame_count: usize::MAX };
+
+        assert_eq!(walker.image(Direction::TopLeft, 0), Some(usize::MAX - 1));
+        assert_eq!(walker.image(Direction::TopLeft, 1), None);
+        assert_eq!(walker.image(Direction::Top, usize::MAX / 4), None);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{ImageType, Result, SgFile, SgImageError, SgImageMetadata};
+
+/// Dimensions of a single isometric tile in pixels.
+///
+/// Tiles are diamonds twice as wide as they are tall (minus the 2 pixel gap between them), so the width follows from the height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Layout of the footprint of an isometric image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IsometricGeometry {
+    /// Size of the tiles the footprint is made of
+    pub tile_size: TileSize,
+    /// Number of tiles along each side of the footprint
+    pub size: u16,
+}
+
+impl TileSize {
+    /// Tiles used by most of the games.
+    pub const SMALL: TileSize = TileSize::from_height(30);
+    /// Tiles used for some of the sets in Zeus and Emperor.
+    pub const LARGE: TileSize = TileSize::from_height(40);
+    /// Tile sizes tried by default, in order.
+    pub const DEFAULTS: [TileSize; 2] = [TileSize::SMALL, TileSize::LARGE];
+
+    /// Tile of the given height, the width saturates for heights too large to fit a tile (see [`TileSize::is_valid`]).
+    pub const fn from_height(height: u16) -> Self {
+        let width = (height as u32 * 2).saturating_sub(2);
+        TileSize { width: if width > u16::MAX as u32 { u16::MAX } else { width as u16 }, height }
+    }
+
+    /// Length of the pixel data of a single tile in bytes.
+    pub fn length(&self) -> u32 {
+        self.height as u32 * self.height as u32 * 2
+    }
+
+    /// Checks if the tile is a diamond that can be decoded.
+    pub fn is_valid(&self) -> bool {
+        self.height >= 2 && self.height.is_multiple_of(2) && self.width as u32 == self.height as u32 * 2 - 2
+    }
+}
+
+impl IsometricGeometry {
+    /// Height of the footprint in pixels.
+    pub fn height(&self) -> u32 {
+        self.size as u32 * self.tile_size.height as u32
+    }
+
+    /// Width of the footprint in pixels.
+    pub fn width(&self) -> u32 {
+        (self.size as u32 * (self.tile_size.width as u32 + 2)).saturating_sub(2)
+    }
+
+    /// Length of the pixel data of the footprint in bytes.
+    pub fn length(&self) -> u32 {
+        self.size as u32 * self.size as u32 * self.tile_size.length()
+    }
+}
+
+impl SgImageMetadata {
+    /// Layout of the footprint of this isometric image.
+    ///
+    /// If the size in tiles is stored in the image (see [`SgImageMetadata::size_in_tiles`]) the tile size follows from it.
+    /// Otherwise the first of `tile_sizes` (or [`TileSize::DEFAULTS`] if empty) evenly dividing the footprint is used.
+    /// Either way the result is checked against the dimensions and `uncompressed_length` of the image.
+    pub fn isometric_geometry(&self, tile_sizes: &[TileSize]) -> Result<IsometricGeometry> {
+        if self.kind() != ImageType::Isometric {
+            return Err(SgImageError::NotIsometric(self.image_type));
+        }
+
+        let tile_sizes = if tile_sizes.is_empty() { &TileSize::DEFAULTS[..] } else { tile_sizes };
+        let invalid_dimensions = || SgImageError::InvalidDimensions { width: self.width, height: self.height };
+        let height = (self.width as u32 + 2) / 2;
+
+        let geometry = if self.size_in_tiles() != 0 {
+            let size = self.size_in_tiles() as u16;
+            let tile_height = u16::try_from(height / size as u32).map_err(|_| invalid_dimensions())?;
+            IsometricGeometry { tile_size: TileSize::from_height(tile_height), size }
+        } else {
+            let tile_size =
+                tile_sizes.iter().find(|tile_size| tile_size.height != 0 && height.is_multiple_of(tile_size.height as u32)).ok_or_else(invalid_dimensions)?;
+            let size = u16::try_from(height / tile_size.height as u32).map_err(|_| invalid_dimensions())?;
+            IsometricGeometry { tile_size: *tile_size, size }
+        };
+
+        if geometry.size == 0 || !geometry.tile_size.is_valid() || geometry.width() != self.width as u32 || geometry.height() > self.height as u32 {
+            return Err(invalid_dimensions());
+        }
+
+        if geometry.length() != self.uncompressed_length {
+            return Err(SgImageError::ImageDataLengthMismatch { expected: geometry.length() as u64, actual: self.uncompressed_length as u64 });
+        }
+
+        Ok(geometry)
+    }
+}
+
+impl SgFile {
+    /// Layout of the footprint of the given isometric image, using the tile sizes of this file.
+    pub fn isometric_geometry(&self, image: &SgImageMetadata) -> Result<IsometricGeometry> {
+        image.isometric_geometry(&self.profile().tile_sizes)
+    }
+
+    /// Size of the given isometric image in tiles, using the tile sizes of this file.
+    pub fn footprint_size(&self, image: &SgImageMetadata) -> Option<u16> {
+        image.footprint_size(&self.profile().tile_sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodeOptions, SgFile, SgImageError, SgImageMetadata, TileSize, VecImageBuilderFactory};
+
+    /// Footprint that is either 4 small tiles or 3 large tiles wide.
+    fn ambiguous_image() -> SgImageMetadata {
+        SgImageMetadata { width: 238, height: 120, image_type: 30, uncompressed_length: 28800, ..Default::default() }
+    }
+
+    #[test]
+    fn tile_sizes_are_tried_in_order() {
+        let image = ambiguous_image();
+
+        assert_eq!(image.footprint_size(&[]), Some(4));
+        assert_eq!(image.footprint_size(&[TileSize::LARGE]), Some(3));
+        assert_eq!(image.footprint_size(&[TileSize::from_height(50)]), None);
+    }
+
+    #[test]
+    fn oversized_tiles_are_rejected() {
+        let mut image = SgImageMetadata { width: 65534, height: 100, image_type: 30, length: 1, ..Default::default() };
+        image.flags[3] = 1;
+
+        assert!(matches!(image.isometric_geometry(&[]), Err(SgImageError::InvalidDimensions { width: 65534, height: 100 })));
+        assert!(image.load_image_from_raw_data(&[0], &VecImageBuilderFactory).is_err());
+        assert!(!TileSize::from_height(40000).is_valid());
+    }
+
+    #[test]
+    fn files_use_their_own_tile_sizes() {
+        let sg_file = SgFile { tile_sizes: vec![TileSize::LARGE], ..Default::default() };
+        let mut image = ambiguous_image();
+
+        assert_eq!(sg_file.footprint_size(&image), Some(3));
+
+        let pixels: Vec<u8> = (0..238 * 120).flat_map(|position| [(position % 32 * 8) as u8, 0, 0, 0xff]).collect();
+        let data = sg_file.encode_image(&mut image, &pixels, false).unwrap();
+        let options = DecodeOptions::default().with_tile_sizes(sg_file.profile().tile_sizes);
+        let decoded = image.load_image_from_raw_data_with_options(&data, &VecImageBuilderFactory, &options).unwrap();
+
+        // Pixels outside of the footprint are encoded in the top part, so every pixel survives
+        assert_eq!(decoded, pixels);
+
+        // Decoding through the file picks up its tile sizes as well
+        let decoded = sg_file.load_image_from_slice(&image, &data, &VecImageBuilderFactory, &DecodeOptions::default()).unwrap();
+        assert_eq!(decoded, pixels);
+    }
+}
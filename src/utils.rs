@@ -1,5 +1,5 @@
 use crate::{Result, SgImageError};
-use std::io::Read;
+use std::io::{self, Error, ErrorKind, Read, Write};
 use std::str;
 
 pub trait ReadHelper {
@@ -13,6 +13,8 @@ pub trait ReadHelper {
 
     fn read_string(&mut self, length: usize) -> Result<String>;
 
+    fn read_vec(&mut self, length: usize) -> Result<Vec<u8>>;
+
     fn read_bytes<const LENGTH: usize>(&mut self) -> Result<[u8; LENGTH]>
     where
         [u8; LENGTH]: Default;
@@ -48,10 +50,22 @@ impl<R: Read> ReadHelper for R {
 
         self.read_exact(&mut tmp)?;
 
-        return match str::from_utf8(&tmp) {
+        match str::from_utf8(&tmp) {
             Ok(str) => Ok(String::from(str.split(char::from(0)).nth(0).unwrap())),
             Err(err) => Err(SgImageError::Utf8Error(err)),
-        };
+        }
+    }
+
+    fn read_vec(&mut self, length: usize) -> Result<Vec<u8>> {
+        // Avoid allocating everything upfront, in case the length comes from a corrupted file
+        let mut result = Vec::new();
+        self.take(length as u64).read_to_end(&mut result)?;
+
+        if result.len() != length {
+            return Err(SgImageError::IoError(Error::from(ErrorKind::UnexpectedEof)));
+        }
+
+        Ok(result)
     }
 
     fn read_bytes<const LENGTH: usize>(&mut self) -> Result<[u8; LENGTH]>
@@ -63,3 +77,99 @@ impl<R: Read> ReadHelper for R {
         Ok(result)
     }
 }
+
+pub trait WriteHelper {
+    fn write_u8(&mut self, value: u8) -> Result<()>;
+
+    fn write_u16_le(&mut self, value: u16) -> Result<()>;
+
+    fn write_u32_le(&mut self, value: u32) -> Result<()>;
+
+    fn write_i32_le(&mut self, value: i32) -> Result<()>;
+
+    fn write_string(&mut self, value: &str, length: usize) -> Result<()>;
+
+    fn write_zeroes(&mut self, length: usize) -> Result<()>;
+}
+
+impl<W: Write> WriteHelper for W {
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write_all(&[value])?;
+        Ok(())
+    }
+
+    fn write_u16_le(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn write_u32_le(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn write_i32_le(&mut self, value: i32) -> Result<()> {
+        self.write_all(&value.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn write_string(&mut self, value: &str, length: usize) -> Result<()> {
+        let bytes = value.as_bytes();
+
+        if bytes.len() > length {
+            return Err(SgImageError::StringTooLong(String::from(value)));
+        }
+
+        self.write_all(bytes)?;
+        self.write_zeroes(length - bytes.len())
+    }
+
+    fn write_zeroes(&mut self, length: usize) -> Result<()> {
+        // Streamed, as the length can come from a corrupted file
+        io::copy(&mut io::repeat(0).take(length as u64), self)?;
+        Ok(())
+    }
+}
+
+/// Cursor over pixel data that is already in memory.
+#[derive(Clone)]
+pub struct SliceReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        SliceReader { data, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let value = *self.data.get(self.position).ok_or_else(Self::eof)?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    #[inline]
+    pub fn read_u16_le(&mut self) -> Result<u16> {
+        let bytes = self.data.get(self.position..self.position + 2).ok_or_else(Self::eof)?;
+        self.position += 2;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn skip(&mut self, length: usize) -> Result<()> {
+        if self.data.len() - self.position < length {
+            return Err(Self::eof());
+        }
+        self.position += length;
+        Ok(())
+    }
+
+    fn eof() -> SgImageError {
+        SgImageError::IoError(Error::from(ErrorKind::UnexpectedEof))
+    }
+}